@@ -1,10 +1,20 @@
 use crate::Bytes;
 use crate::{encrypt, transform};
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use std::ops::RangeInclusive;
+use std::thread;
+
+pub mod cbc;
+pub mod cribdrag;
+pub mod ctr;
+pub mod ecb;
+pub mod lcg;
+pub mod mac;
+pub mod mt19937;
+pub mod oracle;
 
 pub fn single_byte_cipher(input: &Bytes) -> (usize, u8, Bytes) {
-    let dict: Bytes = (0x00..0xFF).collect();
+    let dict: Bytes = (0x00..=0xFF).collect();
 
     let mut high_score = 0;
     let mut key: u8 = 0;
@@ -24,41 +34,235 @@ pub fn single_byte_cipher(input: &Bytes) -> (usize, u8, Bytes) {
     (high_score, key, best)
 }
 
-pub fn repeating_key_cipher(data: &Bytes) -> (Bytes, Bytes) {
-    let len = find_key_length(data, 1)[0];
+/// The result of cracking one line with `detect_single_byte_cipher`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SingleByteMatch {
+    pub line: usize,
+    pub score: usize,
+    pub key: u8,
+    pub plaintext: Bytes,
+}
 
-    let mut map: Vec<Bytes> = vec![Vec::new(); len];
+//
+// Crack every ciphertext as single byte XOR and rank them by score, so the
+// line that was actually encrypted floats to the top.
+//
+// The lines are split evenly across one thread per available core.
+//
+pub fn detect_single_byte_cipher(lines: &[Bytes]) -> Vec<SingleByteMatch> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = lines.len().div_ceil(threads).max(1);
+
+    let mut results: Vec<SingleByteMatch> = thread::scope(|scope| {
+        let handles: Vec<_> = lines
+            .chunks(chunk_size)
+            .enumerate()
+            .map(|(chunk, lines)| {
+                scope.spawn(move || {
+                    lines
+                        .iter()
+                        .enumerate()
+                        .map(|(i, line)| {
+                            let (score, key, plaintext) =
+                                single_byte_cipher(line);
+                            SingleByteMatch {
+                                line: chunk * chunk_size + i,
+                                score,
+                                key,
+                                plaintext,
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    results.sort_by(|a, b| b.score.cmp(&a.score).then(a.line.cmp(&b.line)));
+    results
+}
 
-    let chunks = data.chunks(len);
+pub fn repeating_key_cipher(data: &Bytes) -> (Bytes, Bytes) {
+    match repeating_key_candidates(data, 2..=40, 5, true)
+        .into_iter()
+        .next()
+    {
+        Some((_, key, plaintext)) => (key, plaintext),
+        None => (Bytes::new(), data.to_vec()),
+    }
+}
 
-    for chunk in chunks {
-        for (i, byte) in chunk.iter().enumerate() {
-            map[i].push(*byte);
+//
+// Crack the data under each of the `tries` most likely key lengths within
+// `lengths` and rank the results by the English score of the whole plaintext.
+//
+// Each result is `(score, key, plaintext)`. With `refine` set, keys that
+// repeat a shorter period (e.g. "ICEICE") are collapsed to that period and
+// duplicates are dropped.
+//
+pub fn repeating_key_candidates(
+    data: &Bytes,
+    lengths: RangeInclusive<usize>,
+    tries: usize,
+    refine: bool,
+) -> Vec<(usize, Bytes, Bytes)> {
+    let mut results: Vec<(usize, Bytes, Bytes)> = Vec::new();
+
+    for candidate in find_key_length(data, lengths, KeyLengthMetric::Hamming)
+        .iter()
+        .take(tries)
+    {
+        let mut key = crack_key_columns(data, candidate.len);
+        if refine {
+            key = shortest_period(&key);
         }
+
+        if results.iter().any(|(_, other, _)| *other == key) {
+            continue;
+        }
+
+        let plaintext = encrypt::repeating_key_cipher(data, &key);
+        results.push((english_score(&plaintext), key, plaintext));
     }
 
-    let cipher_key: Bytes = map
+    results.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.len().cmp(&b.1.len())));
+    results
+}
+
+// Find the best single byte key for each column of a given key length.
+pub fn crack_key_columns(data: &Bytes, len: usize) -> Bytes {
+    transpose(data, len)
         .iter()
-        .map(|cipher| single_byte_cipher(cipher))
+        .map(single_byte_cipher)
         .map(|(_, key, _)| key)
-        .collect();
+        .collect()
+}
 
-    let plaintext = encrypt::repeating_key_cipher(data, &cipher_key);
+// Collapse a key to the shortest prefix that repeats to form the whole key.
+fn shortest_period(key: &Bytes) -> Bytes {
+    let period = (1..key.len())
+        .filter(|p| key.len().is_multiple_of(*p))
+        .find(|p| key.iter().enumerate().all(|(i, b)| *b == key[i % p]))
+        .unwrap_or(key.len());
 
-    (cipher_key, plaintext)
+    key[..period].to_vec()
 }
 
-pub fn find_ecb(data: &Vec<Bytes>) -> Bytes {
-    use std::collections::HashSet;
+/// A byte of a recovered key, tagged with how it was found.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum KeyByte {
+    /// Derived from known plaintext, so certain.
+    Known(u8),
+    /// The best single byte guess from the column statistics.
+    Guessed(u8),
+}
 
-    for line in data {
-        let mut set = HashSet::new();
-        let chunks: Vec<&[u8]> = line.chunks_exact(16).collect();
-        for chunk in chunks.iter() {
-            set.insert(chunk.to_vec());
+impl KeyByte {
+    pub fn value(self) -> u8 {
+        match self {
+            KeyByte::Known(byte) | KeyByte::Guessed(byte) => byte,
+        }
+    }
+}
+
+// How many of the likeliest key lengths `crib_key_recovery` tries when it
+// isn't given one.
+const CRIB_KEY_LENGTHS: usize = 5;
+
+//
+// Recover a repeating key using a known fragment of the plaintext that sits
+// at one of `offsets`.
+//
+// Each offset gives a partial key by XORing the crib against the data there.
+// Offsets where the crib disagrees with itself about a key position are
+// thrown out, and the remaining positions are filled in from the column
+// statistics. Without a `key_len` we try the same few likeliest lengths
+// `repeating_key_cipher` does. The offset and length whose plaintext scores
+// best win, and are returned as `(offset, key, plaintext)`.
+//
+pub fn crib_key_recovery(
+    data: &Bytes,
+    key_len: Option<usize>,
+    crib: &Bytes,
+    offsets: &[usize],
+) -> Option<(usize, Vec<KeyByte>, Bytes)> {
+    let lengths: Vec<usize> = match key_len {
+        Some(len) => vec![len],
+        None => find_key_length(data, 2..=40, KeyLengthMetric::Hamming)
+            .iter()
+            .take(CRIB_KEY_LENGTHS)
+            .map(|candidate| candidate.len)
+            .collect(),
+    };
+
+    let mut best: Option<(usize, usize, Vec<KeyByte>, Bytes)> = None;
+
+    for len in lengths.into_iter().filter(|len| *len > 0) {
+        let guesses = crack_key_columns(data, len);
+
+        for &offset in offsets {
+            let key = match crib_key(data, &guesses, crib, offset) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            let values: Bytes = key.iter().map(|k| k.value()).collect();
+            let plaintext = encrypt::repeating_key_cipher(data, &values);
+            let score = english_score(&plaintext);
+
+            if best.as_ref().is_none_or(|(high, ..)| score > *high) {
+                best = Some((score, offset, key, plaintext));
+            }
         }
+    }
+
+    best.map(|(_, offset, key, plaintext)| (offset, key, plaintext))
+}
+
+// Lay the crib over the data at `offset`, and take the key bytes it pins
+// down over the column guesses. `None` if the crib contradicts itself.
+fn crib_key(
+    data: &Bytes,
+    guesses: &Bytes,
+    crib: &Bytes,
+    offset: usize,
+) -> Option<Vec<KeyByte>> {
+    let key_len = guesses.len();
+    if offset >= data.len() {
+        return None;
+    }
+
+    let derived = transform::byte_xor(&data[offset..].to_vec(), crib);
+    let mut known: Vec<Option<u8>> = vec![None; key_len];
 
-        if set.len() < chunks.len() {
+    for (i, byte) in derived.iter().enumerate() {
+        let pos = (offset + i) % key_len;
+        match known[pos] {
+            Some(other) if other != *byte => return None,
+            _ => known[pos] = Some(*byte),
+        }
+    }
+
+    let key = known
+        .iter()
+        .zip(guesses.iter())
+        .map(|(known, guess)| match known {
+            Some(byte) => KeyByte::Known(*byte),
+            None => KeyByte::Guessed(*guess),
+        })
+        .collect();
+
+    Some(key)
+}
+
+pub fn find_ecb(data: &Vec<Bytes>) -> Bytes {
+    for line in data {
+        if has_repeated_blocks(line, 16) {
             return line.to_vec();
         }
     }
@@ -66,27 +270,37 @@ pub fn find_ecb(data: &Vec<Bytes>) -> Bytes {
     Bytes::new()
 }
 
+// ECB encrypts equal blocks to equal ciphertext, so any repeat gives it away.
+pub fn has_repeated_blocks(data: &[u8], block_size: usize) -> bool {
+    use std::collections::HashSet;
+
+    let mut set = HashSet::new();
+    let chunks: Vec<&[u8]> = data.chunks_exact(block_size).collect();
+    for chunk in chunks.iter() {
+        set.insert(chunk.to_vec());
+    }
+
+    set.len() < chunks.len()
+}
+
 const FREQ: &str = "etaoin shrdlucmfwypvbgkjqxz";
 
 fn english_score(input: &Bytes) -> usize {
     let mut score = 0;
 
     for chr in input.iter() {
-        match FREQ.chars().position(|c| c == *chr as char) {
-            Some(i) => score += 30 - i,
-            None => {}
+        if let Some(i) = FREQ.chars().position(|c| c == *chr as char) {
+            score += 30 - i;
         }
     }
 
     score
 }
 
-fn hamming_distance(left: &Bytes, right: &Bytes) -> usize {
+fn hamming_distance(left: &[u8], right: &[u8]) -> usize {
     let mut dist: usize = 0;
 
-    for i in 0..left.len() {
-        let lbyte = left[i];
-        let rbyte = right[i];
+    for (lbyte, rbyte) in left.iter().zip(right) {
         let mut inter = lbyte ^ rbyte;
 
         while inter > 0 {
@@ -100,58 +314,153 @@ fn hamming_distance(left: &Bytes, right: &Bytes) -> usize {
     dist
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
-struct LenDist {
-    len: usize,
-    dist: usize,
+/// Statistic used by `find_key_length` to rank candidate key lengths.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum KeyLengthMetric {
+    /// Average Hamming distance between pairs of leading blocks, in bits per
+    /// byte. Lower is better.
+    Hamming,
+    /// Average index of coincidence of the columns produced by the key
+    /// length. Higher is better.
+    Coincidence,
 }
 
-impl Ord for LenDist {
-    fn cmp(&self, other: &LenDist) -> Ordering {
-        other.dist.cmp(&self.dist)
-    }
+/// A candidate key length along with its score under the chosen metric.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct KeyLength {
+    pub len: usize,
+    pub score: f64,
+    /// Set when a shorter candidate dividing this length scores at least as
+    /// well, which usually means this length is just a repeat of the key.
+    pub multiple_of: Option<usize>,
 }
 
-impl PartialOrd for LenDist {
-    fn partial_cmp(&self, other: &LenDist) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
+// How close a divisor's score needs to be for a longer length to count as a
+// multiple of it, as a fraction of the spread between the best and worst
+// scores in the search.
+const MULTIPLE_TOLERANCE: f64 = 0.1;
+
+// How many blocks `block_distance` compares. The pairs grow with the square
+// of this, so it keeps long inputs fast while leaving enough pairs to tell
+// a key length from its multiples.
+const DISTANCE_BLOCKS: usize = 128;
+
+//
+// Rank every key length in `lengths` by how well it lines up with the data.
+//
+// Lengths that don't leave enough data to measure (two full blocks for
+// Hamming, two bytes per column for coincidence) are skipped, so the result
+// may be shorter than the range, or empty for very short inputs.
+//
+pub fn find_key_length(
+    data: &Bytes,
+    lengths: RangeInclusive<usize>,
+    metric: KeyLengthMetric,
+) -> Vec<KeyLength> {
+    let scored: Vec<(usize, f64)> = lengths
+        .filter(|len| *len > 0)
+        .filter_map(|len| {
+            let score = match metric {
+                KeyLengthMetric::Hamming => block_distance(data, len),
+                KeyLengthMetric::Coincidence => column_coincidence(data, len),
+            };
+            score.map(|score| (len, score))
+        })
+        .collect();
 
-fn find_key_length(data: &Bytes, count: usize) -> Vec<usize> {
-    let mut heap = BinaryHeap::new();
+    let (low, high) = scored.iter().fold(
+        (f64::INFINITY, f64::NEG_INFINITY),
+        |(low, high), &(_, score)| (low.min(score), high.max(score)),
+    );
+    let slack = (high - low) * MULTIPLE_TOLERANCE;
 
-    for len in 2..=40 {
-        let mut iter = data.chunks_exact(len);
-        let mut dists: Vec<f64> = Vec::new();
+    let better = |a: f64, b: f64| match metric {
+        KeyLengthMetric::Hamming => a <= b + slack,
+        KeyLengthMetric::Coincidence => a >= b - slack,
+    };
 
-        loop {
-            match (iter.next(), iter.next()) {
-                (Some(first), Some(second)) => {
-                    dists.push(
-                        hamming_distance(&first.to_vec(), &second.to_vec())
-                            as f64
-                            / len as f64,
-                    );
-                }
-                (_, _) => break,
-            }
+    let mut result: Vec<KeyLength> = scored
+        .iter()
+        .map(|&(len, score)| KeyLength {
+            len,
+            score,
+            multiple_of: scored
+                .iter()
+                .find(|&&(div, other)| {
+                    div < len && len % div == 0 && better(other, score)
+                })
+                .map(|&(div, _)| div),
+        })
+        .collect();
+
+    result.sort_by(|a, b| {
+        let order = a.score.partial_cmp(&b.score).unwrap_or(Ordering::Equal);
+        match metric {
+            KeyLengthMetric::Hamming => order,
+            KeyLengthMetric::Coincidence => order.reverse(),
         }
+    });
 
-        let average: f64 = dists.iter().sum::<f64>() / dists.len() as f64;
-        let dist = (average * 10000.0) as usize;
+    result
+}
 
-        heap.push(LenDist { len, dist });
+// Normalized Hamming distance averaged over every pair of the first
+// `DISTANCE_BLOCKS` full blocks.
+fn block_distance(data: &Bytes, len: usize) -> Option<f64> {
+    let blocks: Vec<&[u8]> =
+        data.chunks_exact(len).take(DISTANCE_BLOCKS).collect();
+    if blocks.len() < 2 {
+        return None;
     }
 
-    let mut result = Vec::new();
-    for _ in 1..=count {
-        if let Some(pair) = heap.pop() {
-            result.push(pair.len);
+    let mut total = 0;
+    for (i, left) in blocks.iter().enumerate() {
+        for right in blocks[i + 1..].iter() {
+            total += hamming_distance(left, right);
         }
     }
 
-    result
+    let pairs = blocks.len() * (blocks.len() - 1) / 2;
+    Some(total as f64 / pairs as f64 / len as f64)
+}
+
+// Index of coincidence averaged over the columns of a key length.
+fn column_coincidence(data: &Bytes, len: usize) -> Option<f64> {
+    if data.len() < len * 2 {
+        return None;
+    }
+
+    let total: f64 = transpose(data, len)
+        .iter()
+        .map(|column| index_of_coincidence(column))
+        .sum();
+
+    Some(total / len as f64)
+}
+
+// The chance that two bytes picked at random from the input are equal.
+fn index_of_coincidence(input: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for byte in input {
+        counts[*byte as usize] += 1;
+    }
+
+    let n = input.len();
+    let matches: usize = counts.iter().map(|c| c * c.saturating_sub(1)).sum();
+    matches as f64 / (n * (n - 1)) as f64
+}
+
+// Split the input into `len` columns, one per key byte.
+fn transpose(data: &Bytes, len: usize) -> Vec<Bytes> {
+    let mut columns: Vec<Bytes> = vec![Vec::new(); len];
+
+    for chunk in data.chunks(len) {
+        for (i, byte) in chunk.iter().enumerate() {
+            columns[i].push(*byte);
+        }
+    }
+
+    columns
 }
 
 #[cfg(test)]
@@ -168,10 +477,183 @@ mod tests {
     fn test_hamming_distance() {
         assert_eq!(
             super::hamming_distance(
-                &"this is a test".as_bytes().to_vec(),
-                &"wokka wokka!!!".as_bytes().to_vec()
+                "this is a test".as_bytes(),
+                "wokka wokka!!!".as_bytes()
             ),
             37
         )
     }
+
+    #[test]
+    fn test_single_byte_cipher_full_range() {
+        let plaintext = "every key byte is a candidate".as_bytes().to_vec();
+
+        for key in [0x00, 0x7f, 0xff] {
+            let data = crate::transform::char_xor(&plaintext, key);
+            let (_, found, decrypted) = super::single_byte_cipher(&data);
+            assert_eq!((found, decrypted), (key, plaintext.clone()));
+        }
+    }
+
+    #[test]
+    fn test_detect_single_byte_cipher() {
+        let lines: Vec<crate::Bytes> = (0..20u8)
+            .map(|i| {
+                let line = format!("line number {} of a short log dump", i);
+                crate::transform::char_xor(&line.into_bytes(), i * 7)
+            })
+            .collect();
+        let ranked = super::detect_single_byte_cipher(&lines);
+
+        assert_eq!(ranked.len(), 20);
+        assert!(ranked.windows(2).all(|w| w[0].score >= w[1].score));
+        for found in ranked.iter() {
+            assert_eq!(found.key, found.line as u8 * 7);
+        }
+
+        assert!(super::detect_single_byte_cipher(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_shortest_period() {
+        let period =
+            |key: &str| super::shortest_period(&key.as_bytes().to_vec());
+
+        assert_eq!(period("ICEICE"), "ICE".as_bytes());
+        assert_eq!(period("ICEIC"), "ICEIC".as_bytes());
+        assert_eq!(period("aaaa"), "a".as_bytes());
+        assert_eq!(period(""), "".as_bytes());
+    }
+
+    #[test]
+    fn test_repeating_key_candidates() {
+        use std::fs;
+
+        let content = fs::read_to_string("6/data.txt").unwrap();
+        let data = crate::encode::b64_to_bytes(&content);
+        let key = "Terminator X: Bring the noise".as_bytes();
+
+        // Without refinement the doubled key length can win outright
+        let raw = super::repeating_key_candidates(&data, 2..=60, 5, false);
+        assert!(raw.iter().any(|(_, k, _)| k.len() == 58));
+
+        let refined = super::repeating_key_candidates(&data, 2..=60, 5, true);
+        assert_eq!(refined[0].1, key);
+        assert!(refined.iter().all(|(_, k, _)| k.len() != 58));
+    }
+
+    #[test]
+    fn test_crib_key_recovery() {
+        use super::KeyByte::*;
+
+        let plaintext = "GIF89a followed by a short caption in plain English"
+            .as_bytes()
+            .to_vec();
+        let key = vec![0x13, 0x37, 0xc0, 0xde, 0x42, 0x99];
+        let data = crate::encrypt::repeating_key_cipher(&plaintext, &key);
+        let crib = "GIF89".as_bytes().to_vec();
+
+        let (offset, recovered, decrypted) =
+            super::crib_key_recovery(&data, Some(6), &crib, &[7, 0, 20])
+                .unwrap();
+
+        assert_eq!(offset, 0);
+        assert_eq!(
+            &recovered[..5],
+            &[
+                Known(0x13),
+                Known(0x37),
+                Known(0xc0),
+                Known(0xde),
+                Known(0x42)
+            ]
+        );
+        assert!(matches!(recovered[5], Guessed(_)));
+        assert_eq!(&decrypted[..5], crib.as_slice());
+    }
+
+    #[test]
+    fn test_crib_key_recovery_unknown_length() {
+        use super::KeyByte::*;
+        use std::fs;
+
+        let content = fs::read_to_string("6/data.txt").unwrap();
+        let data = crate::encode::b64_to_bytes(&content);
+        let key = "Terminator X: Bring the noise".as_bytes();
+        let crib = "I'm back".as_bytes().to_vec();
+
+        let (offset, recovered, _) =
+            super::crib_key_recovery(&data, None, &crib, &[3, 0]).unwrap();
+
+        assert_eq!(offset, 0);
+        assert_eq!(recovered.len(), key.len());
+        assert!(recovered[..8].iter().all(|k| matches!(k, Known(_))));
+        assert!(recovered[8..].iter().all(|k| matches!(k, Guessed(_))));
+        assert_eq!(
+            recovered.iter().map(|k| k.value()).collect::<Vec<u8>>(),
+            key
+        );
+    }
+
+    #[test]
+    fn test_crib_key_recovery_inconsistent() {
+        // A crib longer than the key has to agree with itself
+        let data = "aaaaaaaa".as_bytes().to_vec();
+        let crib = "abcd".as_bytes().to_vec();
+
+        assert_eq!(
+            super::crib_key_recovery(&data, Some(2), &crib, &[0, 2]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_key_length() {
+        use super::KeyLengthMetric::*;
+        use std::fs;
+
+        let content = fs::read_to_string("6/data.txt").unwrap();
+        let data = crate::encode::b64_to_bytes(&content);
+
+        for metric in [Hamming, Coincidence] {
+            let ranked = super::find_key_length(&data, 2..=60, metric);
+            assert_eq!(ranked[0].len, 29);
+            assert_eq!(ranked[0].multiple_of, None);
+
+            let double = ranked.iter().find(|k| k.len == 58).unwrap();
+            assert_eq!(double.multiple_of, Some(29));
+        }
+    }
+
+    #[test]
+    fn test_find_key_length_large_input() {
+        use std::fs;
+
+        // About 200 KB, which is far too many block pairs to compare them all
+        let content = fs::read_to_string("6/data.txt").unwrap();
+        let data = crate::encode::b64_to_bytes(&content).repeat(70);
+
+        let ranked = super::find_key_length(
+            &data,
+            2..=40,
+            super::KeyLengthMetric::Hamming,
+        );
+        assert_eq!(ranked[0].len, 29);
+    }
+
+    #[test]
+    fn test_find_key_length_short_input() {
+        use super::KeyLengthMetric::*;
+        let data = "abcdefg".as_bytes().to_vec();
+
+        let ranked = super::find_key_length(&data, 2..=40, Hamming);
+        assert_eq!(
+            ranked.iter().map(|k| k.len).max(),
+            Some(3),
+            "lengths without two full blocks are skipped"
+        );
+        assert!(ranked.iter().all(|k| k.score.is_finite()));
+
+        assert!(super::find_key_length(&data, 8..=40, Coincidence).is_empty());
+    }
 }
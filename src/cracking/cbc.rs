@@ -0,0 +1,300 @@
+//
+// Attacks on CBC encryption.
+//
+// Flipping a bit in one CBC ciphertext block scrambles that block when
+// decrypted, but flips exactly the same bit in the plaintext of the block
+// after it.
+//
+
+use super::ecb::block_size;
+use super::oracle::{EncryptionOracle, Oracle, PaddingOracle};
+use crate::{padding, transform, Bytes};
+
+//
+// Find where our input starts in an oracle's plaintext by changing its
+// first byte. The first ciphertext block that changes holds the start, and
+// the input length at which a change further in stops affecting that block
+// tells us how far into the block we start.
+//
+pub fn prefix_len(
+    oracle: &mut impl EncryptionOracle,
+    block_size: usize,
+) -> Option<usize> {
+    let a = oracle.query(&vec![b'A']);
+    let b = oracle.query(&vec![b'B']);
+    let first = a
+        .chunks(block_size)
+        .zip(b.chunks(block_size))
+        .position(|(a, b)| a != b)?;
+
+    let range = first * block_size..(first + 1) * block_size;
+    for n in 1..=block_size {
+        let mut a_input = vec![b'A'; n];
+        let mut b_input = a_input.clone();
+        a_input.push(b'A');
+        b_input.push(b'B');
+
+        if oracle.query(&a_input)[range.clone()]
+            == oracle.query(&b_input)[range.clone()]
+        {
+            return Some((first + 1) * block_size - n);
+        }
+    }
+
+    None
+}
+
+//
+// Get `target` into the decrypted plaintext of an oracle that escapes the
+// characters we'd need to write it ourselves.
+//
+// We send a block of filler followed by a harmless stand-in for the target,
+// then XOR the difference between the two into the filler's ciphertext.
+// Returns the forged ciphertext and the index of the block that gets
+// scrambled to make it work.
+//
+pub fn bit_flip(
+    oracle: &mut impl EncryptionOracle,
+    target: &Bytes,
+) -> Option<(Bytes, usize)> {
+    let (block_size, _) = block_size(oracle)?;
+    if target.len() > block_size {
+        return None;
+    }
+
+    let prefix = prefix_len(oracle, block_size)?;
+    let pad = (block_size - prefix % block_size) % block_size;
+    let scrambled = (prefix + pad) / block_size;
+
+    let stand_in: Bytes = vec![b'A'; target.len()];
+    let mut input = vec![b'A'; pad + block_size];
+    input.extend(&stand_in);
+
+    let mut ciphertext = oracle.query(&input);
+    let delta = transform::byte_xor(&stand_in, target);
+    let start = scrambled * block_size;
+
+    for (i, byte) in delta.iter().enumerate() {
+        ciphertext[start + i] ^= byte;
+    }
+
+    Some((ciphertext, scrambled))
+}
+
+//
+// Decrypt CBC ciphertext using only an oracle that says whether the padding
+// came out right.
+//
+// For each block, we make up the block before it and change its bytes from
+// the end backwards until the oracle accepts the padding. That tells us what
+// the block decrypts to before the XOR, and XORing that with the real
+// previous block (the IV for the first one) gives the plaintext.
+//
+// Returns the plaintext without padding and the number of queries it took.
+//
+pub fn padding_oracle(
+    oracle: &mut impl PaddingOracle,
+    iv: &Bytes,
+    ciphertext: &Bytes,
+) -> Option<(Bytes, usize)> {
+    let block_size = iv.len();
+    if block_size == 0 || !ciphertext.len().is_multiple_of(block_size) {
+        return None;
+    }
+
+    let mut oracle = Oracle::counted(&mut *oracle);
+    let mut plaintext = Bytes::new();
+    let mut prev = iv.clone();
+
+    for block in ciphertext.chunks(block_size) {
+        let block = block.to_vec();
+        let intermediate = intermediate_block(&mut oracle, &block)?;
+        plaintext.extend(transform::byte_xor(&intermediate, &prev));
+        prev = block;
+    }
+
+    let plaintext = padding::strip_pkcs7(&plaintext, block_size as u8)?;
+    Some((plaintext, oracle.queries()))
+}
+
+//
+// Use a padding oracle to encrypt, without ever learning the key.
+//
+// Start from any last block and find what it decrypts to before the XOR.
+// Choosing the block before it to be that XORed with the plaintext we want
+// makes the last block decrypt to our plaintext. Then do the same for the
+// block we just chose, all the way back to the IV.
+//
+// Returns `(iv, ciphertext)` for the padded plaintext, or `None` if the
+// block size is one PKCS#7 can't pad.
+//
+pub fn padding_oracle_encrypt(
+    oracle: &mut impl PaddingOracle,
+    plaintext: &Bytes,
+    block_size: usize,
+) -> Option<(Bytes, Bytes)> {
+    if block_size == 0 || block_size > 255 {
+        return None;
+    }
+
+    let padded = padding::pkcs7(plaintext, block_size as u8);
+    let mut blocks: Vec<Bytes> = vec![vec![0; block_size]];
+
+    for target in padded.chunks(block_size).rev() {
+        let intermediate = intermediate_block(oracle, &blocks[0])?;
+        blocks.insert(0, transform::byte_xor(&intermediate, &target.to_vec()));
+    }
+
+    let iv = blocks.remove(0);
+    Some((iv, blocks.concat()))
+}
+
+// What a block decrypts to before it's XORed with the previous block.
+pub fn intermediate_block(
+    oracle: &mut impl PaddingOracle,
+    block: &Bytes,
+) -> Option<Bytes> {
+    let block_size = block.len();
+    let mut intermediate = vec![0; block_size];
+
+    for i in (0..block_size).rev() {
+        let pad = (block_size - i) as u8;
+        let mut forged: Bytes = vec![0; block_size];
+        for j in i + 1..block_size {
+            forged[j] = intermediate[j] ^ pad;
+        }
+
+        let found = (0..=255).find(|guess| {
+            forged[i] = *guess;
+            if !oracle.query(&(forged.clone(), block.clone())) {
+                return false;
+            }
+
+            // For the last byte, "\x02\x02" or longer padding could have
+            // been valid by accident. Changing the byte before it only
+            // keeps the padding valid if ours really ended in "\x01".
+            if i == block_size - 1 && i > 0 {
+                let mut check = forged.clone();
+                check[i - 1] ^= 0xFF;
+                return oracle.query(&(check, block.clone()));
+            }
+
+            true
+        })?;
+
+        intermediate[i] = found ^ pad;
+    }
+
+    Some(intermediate)
+}
+
+//
+// Recover the key from a CBC receiver that uses the key as its IV and leaks
+// the plaintext of anything that doesn't decrypt to ASCII.
+//
+// Sending `C1 || 0 || C1` decrypts the first block with the key as the IV
+// and the third with zeros, so XORing those two plaintext blocks leaves the
+// key. The rest of the original ciphertext goes on the end so the padding
+// still checks out. `ciphertext` needs to be at least three blocks long.
+//
+pub fn key_as_iv(
+    receiver: &mut impl Oracle<Input = Bytes, Output = Option<Bytes>>,
+    ciphertext: &Bytes,
+    block_size: usize,
+) -> Option<Bytes> {
+    if ciphertext.len() < block_size * 3 {
+        return None;
+    }
+
+    let first = &ciphertext[..block_size];
+    let mut forged = first.to_vec();
+    forged.extend(vec![0; block_size]);
+    forged.extend(first);
+    forged.extend(&ciphertext[block_size..]);
+
+    let plaintext = receiver.query(&forged)?;
+    Some(transform::byte_xor(
+        &plaintext[..block_size].to_vec(),
+        &plaintext[block_size * 2..block_size * 3].to_vec(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cracking::oracle::from_fn;
+    use crate::encrypt;
+
+    #[test]
+    fn test_padding_oracle() {
+        use crate::{decrypt, padding};
+
+        let key = vec![1; 16];
+        let iv = vec![2; 16];
+        let mut oracle = from_fn(|(iv, ciphertext): &(Bytes, Bytes)| {
+            let plaintext = decrypt::aes_cbc_padded(ciphertext, &key, iv);
+            padding::strip_pkcs7(&plaintext, 16).is_some()
+        });
+
+        let plaintexts = [
+            "",
+            "exactly sixteen!",
+            // Second to last byte is \x02 once padded, a false positive
+            "fourteen bytes\x02",
+            "a few blocks of text to get through in one go",
+        ];
+
+        for plaintext in plaintexts.iter() {
+            let plaintext = plaintext.as_bytes().to_vec();
+            let ciphertext = encrypt::aes_cbc(&plaintext, &key, &iv);
+
+            let (recovered, queries) =
+                padding_oracle(&mut oracle, &iv, &ciphertext).unwrap();
+            assert_eq!(recovered, plaintext);
+            assert!(queries <= ciphertext.len() * 256 + ciphertext.len() / 16);
+        }
+    }
+
+    #[test]
+    fn test_padding_oracle_encrypt() {
+        use crate::{decrypt, padding};
+
+        let key = crate::random::bytes(16);
+        let mut oracle = from_fn(|(iv, ciphertext): &(Bytes, Bytes)| {
+            let plaintext = decrypt::aes_cbc_padded(ciphertext, &key, iv);
+            padding::strip_pkcs7(&plaintext, 16).is_some()
+        });
+
+        for plaintext in
+            ["", "admin=true", "a message the key holder never wrote"].iter()
+        {
+            let plaintext = plaintext.as_bytes().to_vec();
+            let (iv, ciphertext) =
+                padding_oracle_encrypt(&mut oracle, &plaintext, 16).unwrap();
+
+            assert_eq!(ciphertext.len(), (plaintext.len() / 16 + 1) * 16);
+            assert_eq!(decrypt::aes_cbc(&ciphertext, &key, &iv), plaintext);
+        }
+
+        let plaintext = b"admin=true".to_vec();
+        assert!(padding_oracle_encrypt(&mut oracle, &plaintext, 0).is_none());
+        assert!(padding_oracle_encrypt(&mut oracle, &plaintext, 256).is_none());
+    }
+
+    #[test]
+    fn test_prefix_len() {
+        let key = vec![1; 16];
+        let iv = vec![2; 16];
+
+        for len in [0, 1, 15, 16, 17, 40].iter() {
+            let mut oracle = from_fn(|input: &Bytes| {
+                let mut plaintext = vec![b'A'; *len];
+                plaintext.extend(input);
+                plaintext.extend("suffix".as_bytes());
+                encrypt::aes_cbc(&plaintext, &key, &iv)
+            });
+
+            assert_eq!(prefix_len(&mut oracle, 16), Some(*len));
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//
+// Crib dragging against a many-time pad.
+//
+// When several messages are XORed against the same keystream, XORing two
+// ciphertexts cancels the key and leaves the XOR of the two plaintexts. If
+// we guess a word (the crib) in one message, XORing it in at the right spot
+// reveals the other message at that spot. Sliding the crib along every
+// offset and keeping the fragments that look like English is crib dragging.
+//
+
+use super::english_score;
+use crate::transform;
+use crate::Bytes;
+use std::cmp::Reverse;
+
+/// A spot where a crib produced a plausible fragment.
+///
+/// If either message in `pair` holds the crib at `offset`, then the other
+/// one holds `fragment` there.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CribMatch {
+    pub score: usize,
+    pub pair: (usize, usize),
+    pub offset: usize,
+    pub fragment: Bytes,
+}
+
+/// Ciphertexts sharing a keystream, plus the keystream bytes recovered so far.
+pub struct CribDragger {
+    ciphertexts: Vec<Bytes>,
+    keystream: Vec<Option<u8>>,
+}
+
+impl CribDragger {
+    pub fn new(ciphertexts: Vec<Bytes>) -> CribDragger {
+        let len = ciphertexts.iter().map(|c| c.len()).max().unwrap_or(0);
+
+        CribDragger {
+            ciphertexts,
+            keystream: vec![None; len],
+        }
+    }
+
+    /// Slide the crib across the XOR of every pair of ciphertexts and rank
+    /// the printable fragments it uncovers, best first.
+    pub fn drag(&self, crib: &Bytes) -> Vec<CribMatch> {
+        let mut matches = Vec::new();
+
+        for i in 0..self.ciphertexts.len() {
+            for j in i + 1..self.ciphertexts.len() {
+                let xored = transform::byte_xor(
+                    &self.ciphertexts[i],
+                    &self.ciphertexts[j],
+                );
+
+                for offset in 0..=xored.len().saturating_sub(crib.len()) {
+                    let fragment =
+                        transform::byte_xor(&xored[offset..].to_vec(), crib);
+
+                    if fragment.len() < crib.len() || !printable(&fragment) {
+                        continue;
+                    }
+
+                    matches.push(CribMatch {
+                        score: english_score(&fragment),
+                        pair: (i, j),
+                        offset,
+                        fragment,
+                    });
+                }
+            }
+        }
+
+        matches.sort_by_key(|m| Reverse(m.score));
+        matches
+    }
+
+    /// Declare that `message` reads `plaintext` at `offset`, fixing those
+    /// keystream bytes. Anything past the end of the message is ignored.
+    pub fn lock(&mut self, message: usize, offset: usize, plaintext: &Bytes) {
+        let ciphertext = &self.ciphertexts[message];
+
+        for (i, byte) in plaintext.iter().enumerate() {
+            match ciphertext.get(offset + i) {
+                Some(c) => self.keystream[offset + i] = Some(c ^ byte),
+                None => break,
+            }
+        }
+    }
+
+    /// Forget the keystream bytes in `offset..offset + len`.
+    pub fn unlock(&mut self, offset: usize, len: usize) {
+        for byte in self.keystream.iter_mut().skip(offset).take(len) {
+            *byte = None;
+        }
+    }
+
+    pub fn keystream(&self) -> &[Option<u8>] {
+        &self.keystream
+    }
+
+    /// The known bytes of a message under the locked keystream.
+    pub fn plaintext(&self, message: usize) -> Vec<Option<u8>> {
+        self.ciphertexts[message]
+            .iter()
+            .zip(self.keystream.iter())
+            .map(|(c, k)| k.map(|k| c ^ k))
+            .collect()
+    }
+
+    /// A message with unknown bytes shown as underscores.
+    pub fn render(&self, message: usize) -> String {
+        self.plaintext(message)
+            .iter()
+            .map(|byte| byte.map_or('_', |b| b as char))
+            .collect()
+    }
+}
+
+fn printable(input: &Bytes) -> bool {
+    input
+        .iter()
+        .all(|b| (0x20..0x7F).contains(b) || *b == b'\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CribDragger;
+
+    fn dragger() -> CribDragger {
+        let keystream: Vec<u8> =
+            (0..64u32).map(|i| (i * 167 + 59) as u8 ^ 0xA5).collect();
+
+        let messages = [
+            "we attack the castle at dawn",
+            "bring the rope and the ladder",
+            "the guards change at midnight",
+        ];
+
+        CribDragger::new(
+            messages
+                .iter()
+                .map(|m| {
+                    crate::transform::byte_xor(
+                        &m.as_bytes().to_vec(),
+                        &keystream,
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_drag() {
+        let dragger = dragger();
+        let matches = dragger.drag(&"the guards".as_bytes().to_vec());
+
+        // Message 2 starts with the crib, so message 0 and 1 show through
+        assert!(matches.iter().any(|m| m.pair == (0, 2)
+            && m.offset == 0
+            && m.fragment == "we attack ".as_bytes()));
+        assert!(matches.iter().any(|m| m.pair == (1, 2)
+            && m.offset == 0
+            && m.fragment == "bring the ".as_bytes()));
+    }
+
+    #[test]
+    fn test_lock() {
+        let mut dragger = dragger();
+        assert_eq!(dragger.render(0), "_".repeat(28));
+
+        dragger.lock(2, 0, &"the guards".as_bytes().to_vec());
+        assert_eq!(dragger.render(0), format!("we attack {}", "_".repeat(18)));
+        assert_eq!(dragger.render(1), format!("bring the {}", "_".repeat(19)));
+
+        dragger.unlock(5, 100);
+        assert_eq!(dragger.render(1), format!("bring{}", "_".repeat(24)));
+        assert_eq!(dragger.keystream().iter().flatten().count(), 5);
+    }
+}
@@ -0,0 +1,116 @@
+//
+// Attacks on CTR encryption.
+//
+// CTR with a fixed nonce produces the same keystream every time, so every
+// ciphertext is the plaintext XORed against the same key. Lining them up,
+// each column is single byte XOR just like repeating key XOR.
+//
+
+use super::oracle::{EditOracle, EncryptionOracle};
+use super::{crack_key_columns, single_byte_cipher};
+use crate::{transform, Bytes};
+
+//
+// Recover the keystream shared by ciphertexts encrypted under the same nonce,
+// up to the length of the shortest one.
+//
+// Truncating every ciphertext to the same length and gluing them together
+// makes it repeating key XOR with a known key length.
+//
+pub fn fixed_nonce_truncated(ciphertexts: &[Bytes]) -> Bytes {
+    let len = match ciphertexts.iter().map(|c| c.len()).min() {
+        Some(len) if len > 0 => len,
+        _ => return Bytes::new(),
+    };
+
+    let joined: Bytes = ciphertexts
+        .iter()
+        .flat_map(|c| c[..len].iter().cloned())
+        .collect();
+
+    crack_key_columns(&joined, len)
+}
+
+//
+// Recover the keystream as far as the longest ciphertext reaches.
+//
+// Each position is cracked using only the ciphertexts long enough to cover
+// it, so the bytes get less reliable towards the end where fewer
+// ciphertexts are left.
+//
+pub fn fixed_nonce_keystream(ciphertexts: &[Bytes]) -> Bytes {
+    let len = ciphertexts.iter().map(|c| c.len()).max().unwrap_or(0);
+
+    (0..len)
+        .map(|i| {
+            let column: Bytes = ciphertexts
+                .iter()
+                .filter_map(|c| c.get(i))
+                .cloned()
+                .collect();
+            let (_, key, _) = single_byte_cipher(&column);
+            key
+        })
+        .collect()
+}
+
+//
+// Decrypt a CTR ciphertext through an oracle that lets us edit it.
+//
+// Editing re-encrypts our text with the keystream at that position. If the
+// text we write is the ciphertext itself, the XOR with the keystream undoes
+// the encryption and the oracle hands us the plaintext.
+//
+pub fn edit_recover(oracle: &mut impl EditOracle, ciphertext: &Bytes) -> Bytes {
+    oracle.query(&(ciphertext.clone(), 0, ciphertext.clone()))
+}
+
+//
+// Get `target` into the plaintext of a CTR oracle that escapes the
+// characters we'd need to write it ourselves.
+//
+// Flipping a ciphertext bit flips the same plaintext bit and nothing else,
+// so we send a stand-in of the same length and XOR in the difference.
+//
+pub fn bit_flip(
+    oracle: &mut impl EncryptionOracle,
+    target: &Bytes,
+) -> Option<Bytes> {
+    // Our input starts at the first byte that depends on it
+    let a = oracle.query(&vec![b'A']);
+    let b = oracle.query(&vec![b'B']);
+    let prefix = a.iter().zip(b.iter()).position(|(a, b)| a != b)?;
+
+    let stand_in: Bytes = vec![b'A'; target.len()];
+    let mut ciphertext = oracle.query(&stand_in);
+    let delta = transform::byte_xor(&stand_in, target);
+
+    for (i, byte) in delta.iter().enumerate() {
+        ciphertext[prefix + i] ^= byte;
+    }
+
+    Some(ciphertext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cracking::oracle::{from_fn, Oracle};
+    use crate::encrypt;
+
+    #[test]
+    fn test_edit_recover() {
+        let key = vec![9; 16];
+        let plaintext = "the edit oracle gives it all away".as_bytes().to_vec();
+        let ciphertext = encrypt::aes_ctr(&plaintext, &key, 7);
+
+        let mut oracle =
+            from_fn(|(ciphertext, offset, newtext): &(_, _, _)| {
+                encrypt::aes_ctr_edit(ciphertext, &key, 7, *offset, newtext)
+            })
+            .counted();
+
+        assert_eq!(edit_recover(&mut oracle, &ciphertext), plaintext);
+        assert_eq!(oracle.queries(), 1);
+    }
+}
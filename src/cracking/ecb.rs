@@ -0,0 +1,539 @@
+//
+// Attacks on ECB encryption oracles.
+//
+// ECB encrypts every block on its own, so if we control part of the input
+// we can line it up against block boundaries and watch which ciphertext
+// blocks change.
+//
+
+use super::has_repeated_blocks;
+use super::oracle::EncryptionOracle;
+use crate::{padding, Bytes};
+
+// Give up looking for a block size beyond this many bytes of input.
+const MAX_BLOCK_SIZE: usize = 256;
+
+/// What `profile` learned about an encryption oracle.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct OracleProfile {
+    pub block_size: usize,
+    pub ecb: bool,
+    /// Length of the hidden data placed before our input. Only known for
+    /// ECB oracles.
+    pub prefix_len: Option<usize>,
+    /// Length of the hidden data placed after our input. Only known for
+    /// ECB oracles.
+    pub suffix_len: Option<usize>,
+    /// Combined length of the prefix and suffix.
+    pub overhead: usize,
+}
+
+//
+// Work out the block size, mode, and hidden prefix and suffix lengths of an
+// oracle that encrypts `prefix || input || suffix` with PKCS#7 padding.
+//
+// Returns `None` if the ciphertext doesn't grow in whole blocks, e.g. for a
+// stream cipher.
+//
+pub fn profile(oracle: &mut impl EncryptionOracle) -> Option<OracleProfile> {
+    let (block_size, overhead) = block_size(oracle)?;
+
+    // Three blocks of filler always covers two full aligned blocks
+    let filler = vec![b'A'; block_size * 3];
+    let ecb = has_repeated_blocks(&oracle.query(&filler), block_size);
+
+    // A prefix longer than all the hidden data means the oracle isn't
+    // giving consistent answers
+    let prefix_len = if ecb {
+        prefix_len(oracle, block_size).filter(|prefix| *prefix <= overhead)
+    } else {
+        None
+    };
+
+    Some(OracleProfile {
+        block_size,
+        ecb,
+        prefix_len,
+        suffix_len: prefix_len.and_then(|prefix| overhead.checked_sub(prefix)),
+        overhead,
+    })
+}
+
+//
+// Feed in longer and longer input until the ciphertext grows. The jump is
+// one block, and it happens when the plaintext fills its last block
+// exactly, which tells us how much hidden data there is in total.
+//
+// Returns `(block_size, overhead)`.
+//
+pub fn block_size(
+    oracle: &mut impl EncryptionOracle,
+) -> Option<(usize, usize)> {
+    let initial = oracle.query(&Bytes::new()).len();
+
+    for len in 1..=MAX_BLOCK_SIZE {
+        let size = oracle.query(&vec![b'A'; len]).len();
+        if size > initial {
+            // Growing a byte at a time means there are no blocks at all
+            if size - initial == 1 {
+                return None;
+            }
+            return Some((size - initial, initial.checked_sub(len)?));
+        }
+    }
+
+    None
+}
+
+//
+// Find how many bytes the oracle puts in front of our input.
+//
+// We pad our input with `pad` bytes and then two identical blocks. Once the
+// pad pushes our blocks onto a boundary they encrypt to the same thing,
+// and the block they start at gives away the prefix length. Doing it with
+// two different fillers tells our blocks apart from ones that repeat in the
+// hidden data, or because the prefix happens to end with the filler byte:
+// only ours change with the filler. We ask again a few times before
+// trusting the answer, and give up if it moves, as it will when the prefix
+// changes length between queries.
+//
+pub fn prefix_len(
+    oracle: &mut impl EncryptionOracle,
+    block_size: usize,
+) -> Option<usize> {
+    for pad in 0..block_size {
+        if let Some(i) = our_blocks(oracle, block_size, pad) {
+            let stable = (0..PREFIX_CHECKS)
+                .all(|_| our_blocks(oracle, block_size, pad) == Some(i));
+
+            return if stable {
+                (i * block_size).checked_sub(pad)
+            } else {
+                None
+            };
+        }
+    }
+
+    None
+}
+
+// How many more times `prefix_len` checks the offset it found.
+const PREFIX_CHECKS: usize = 4;
+
+// Where our two identical blocks start after `pad` bytes of padding, if
+// they line up with a block boundary.
+fn our_blocks(
+    oracle: &mut impl EncryptionOracle,
+    block_size: usize,
+    pad: usize,
+) -> Option<usize> {
+    let first = repeated_blocks(oracle, block_size, pad, b'A');
+    let second = repeated_blocks(oracle, block_size, pad, b'B');
+
+    first
+        .iter()
+        .find(|(a, a_block)| {
+            second
+                .iter()
+                .any(|(b, b_block)| a == b && a_block != b_block)
+        })
+        .map(|(i, _)| *i)
+}
+
+// Index and contents of every block that's equal to the one after it.
+fn repeated_blocks(
+    oracle: &mut impl EncryptionOracle,
+    block_size: usize,
+    pad: usize,
+    filler: u8,
+) -> Vec<(usize, Bytes)> {
+    let mut input = vec![b'\0'; pad];
+    input.extend(vec![filler; block_size * 2]);
+
+    let ciphertext = oracle.query(&input);
+    let blocks: Vec<&[u8]> = ciphertext.chunks_exact(block_size).collect();
+
+    blocks
+        .windows(2)
+        .enumerate()
+        .filter(|(_, pair)| pair[0] == pair[1])
+        .map(|(i, pair)| (i, pair[0].to_vec()))
+        .collect()
+}
+
+//
+// Recover the secret an ECB oracle appends to our input, one byte at a time.
+//
+// Pad our input so the next unknown byte is the last byte of a block, then
+// compare that block against all 256 blocks we get by guessing it. The
+// oracle may put a fixed prefix in front of our input. If `random_prefix`
+// is set the prefix can change length on every query, and we keep asking
+// until our input lands on a block boundary.
+//
+pub fn byte_at_a_time(
+    oracle: &mut impl EncryptionOracle,
+    random_prefix: bool,
+) -> Option<Bytes> {
+    let (block_size, aligner) = if random_prefix {
+        let block_size = common_block_size(oracle)?;
+
+        // Wherever the prefix ends, three blocks of filler cover two
+        // aligned ones
+        let filler = vec![b'A'; block_size * 3];
+        if !has_repeated_blocks(&oracle.query(&filler), block_size) {
+            return None;
+        }
+        (block_size, Aligner::Marker)
+    } else {
+        let profile = profile(oracle)?;
+        if !profile.ecb {
+            return None;
+        }
+
+        let block_size = profile.block_size;
+        let prefix = profile.prefix_len?;
+        let pad = (block_size - prefix % block_size) % block_size;
+        let aligner = Aligner::Fixed {
+            pad,
+            skip: prefix + pad,
+        };
+        (block_size, aligner)
+    };
+
+    let mut secret = Bytes::new();
+
+    loop {
+        let i = secret.len();
+        let filler = vec![b'A'; block_size - 1 - i % block_size];
+        let start = i / block_size * block_size;
+
+        let ciphertext = aligner.encrypt(oracle, block_size, &filler)?;
+        let target = match ciphertext.get(start..start + block_size) {
+            Some(block) => block.to_vec(),
+            None => break,
+        };
+
+        // Every possible last byte in one query, one block each
+        let mut known = filler.clone();
+        known.extend(&secret);
+        let window = &known[known.len() - (block_size - 1)..];
+
+        let mut guesses = Bytes::new();
+        for byte in 0..=255 {
+            guesses.extend(window);
+            guesses.push(byte);
+        }
+
+        let dictionary = aligner.encrypt(oracle, block_size, &guesses)?;
+        let found = dictionary
+            .chunks_exact(block_size)
+            .take(256)
+            .position(|block| block == target.as_slice());
+
+        match found {
+            Some(byte) => secret.push(byte as u8),
+            None => break,
+        }
+    }
+
+    // The last byte we matched was the first byte of padding
+    secret.pop();
+    Some(secret)
+}
+
+//
+// Block size of an oracle whose output length jumps around between queries.
+// Every ciphertext is a whole number of blocks, and inputs spanning a full
+// block are sure to produce two neighbouring multiples.
+//
+fn common_block_size(oracle: &mut impl EncryptionOracle) -> Option<usize> {
+    let gcd = (0..MAX_BLOCK_SIZE)
+        .map(|len| oracle.query(&vec![b'A'; len]).len())
+        .fold(0, gcd);
+
+    if gcd > 1 {
+        Some(gcd)
+    } else {
+        None
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// How to get a ciphertext that starts right where our input does.
+enum Aligner {
+    /// The prefix never changes, so pad it out and skip past it.
+    Fixed { pad: usize, skip: usize },
+    /// Lead with two pairs of marker blocks and retry until they line up,
+    /// giving up after `MARKER_ATTEMPTS` tries per byte of block size.
+    Marker,
+}
+
+impl Aligner {
+    fn encrypt(
+        &self,
+        oracle: &mut impl EncryptionOracle,
+        block_size: usize,
+        input: &[u8],
+    ) -> Option<Bytes> {
+        match *self {
+            Aligner::Fixed { pad, skip } => {
+                let mut query = vec![0; pad];
+                query.extend(input);
+                oracle.query(&query).get(skip..).map(|rest| rest.to_vec())
+            }
+            Aligner::Marker => {
+                // Shift by a different amount each try in case the prefix
+                // doesn't change after all
+                let attempts = block_size * MARKER_ATTEMPTS;
+                for pad in (0..block_size).cycle().take(attempts) {
+                    let mut query = vec![0; pad];
+                    for marker in MARKERS.iter() {
+                        query.extend(vec![*marker; block_size * 2]);
+                    }
+                    query.extend(input);
+
+                    let ciphertext = oracle.query(&query);
+                    let blocks: Vec<&[u8]> =
+                        ciphertext.chunks_exact(block_size).collect();
+
+                    let found = blocks.windows(4).position(|quad| {
+                        quad[0] == quad[1]
+                            && quad[2] == quad[3]
+                            && quad[1] != quad[2]
+                    });
+
+                    if let Some(i) = found {
+                        return Some(
+                            ciphertext[(i + 4) * block_size..].to_vec(),
+                        );
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+// If the prefix happens to end in the first marker byte, the first pair can
+// match one byte off. The second pair ends in a different byte, so it can't.
+const MARKERS: [u8; 2] = [0xA5, 0x5A];
+
+// A random prefix lines our markers up about once every `block_size` tries,
+// so this many rounds of that failing means they never will.
+const MARKER_ATTEMPTS: usize = 32;
+
+//
+// Swap the last `replace_len` bytes of an ECB oracle's plaintext for
+// `replacement`, using only ciphertext blocks the oracle gave us.
+//
+// First we pad our input so `replacement` (with its own PKCS#7 padding)
+// sits on block boundaries, and cut those blocks out. Then we pick an input
+// length that leaves just the bytes to replace in the final block, drop
+// that block, and paste ours on the end.
+//
+pub fn cut_and_paste(
+    oracle: &mut impl EncryptionOracle,
+    replace_len: usize,
+    replacement: &Bytes,
+) -> Option<Bytes> {
+    let profile = profile(oracle)?;
+    let block_size = profile.block_size;
+    let prefix = profile.prefix_len?;
+    let suffix = profile.suffix_len?;
+
+    // PKCS#7 can't pad blocks longer than 255 bytes
+    if !profile.ecb || replace_len > suffix || block_size > 255 {
+        return None;
+    }
+
+    let pad = (block_size - prefix % block_size) % block_size;
+    let padded = padding::pkcs7(replacement, block_size as u8);
+
+    let mut input = vec![b'A'; pad];
+    input.extend(&padded);
+    let start = prefix + pad;
+    let pasted = oracle.query(&input)[start..start + padded.len()].to_vec();
+
+    let kept = prefix + suffix - replace_len;
+    let len = (block_size - kept % block_size) % block_size;
+    let mut forged = oracle.query(&vec![b'A'; len])[..kept + len].to_vec();
+
+    forged.extend(pasted);
+    Some(forged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cracking::oracle::from_fn;
+    use crate::encrypt;
+
+    fn ecb_oracle(prefix: &str, suffix: &str) -> impl EncryptionOracle {
+        let key = "YELLOW SUBMARINE".as_bytes().to_vec();
+        let prefix = prefix.as_bytes().to_vec();
+        let suffix = suffix.as_bytes().to_vec();
+
+        from_fn(move |input: &Bytes| {
+            let mut plaintext = prefix.clone();
+            plaintext.extend(input);
+            plaintext.extend(&suffix);
+            encrypt::aes_ecb(&plaintext, &key)
+        })
+    }
+
+    #[test]
+    fn test_profile() {
+        let cases = [
+            ("", ""),
+            ("", "a secret suffix"),
+            ("prefix", "suffix"),
+            ("exactly sixteen!", "and a suffix that's quite long"),
+            ("a prefix ending in AAAAAAA", "x"),
+            // Two identical blocks in the prefix shouldn't look like ours
+            ("0123456789abcdef0123456789abcdef and then some", "suffix"),
+        ];
+
+        for (prefix, suffix) in cases.iter() {
+            let profile = super::profile(&mut ecb_oracle(prefix, suffix));
+
+            assert_eq!(
+                profile,
+                Some(OracleProfile {
+                    block_size: 16,
+                    ecb: true,
+                    prefix_len: Some(prefix.len()),
+                    suffix_len: Some(suffix.len()),
+                    overhead: prefix.len() + suffix.len(),
+                }),
+                "prefix {:?}, suffix {:?}",
+                prefix,
+                suffix
+            );
+        }
+    }
+
+    #[test]
+    fn test_profile_random_prefix() {
+        use crate::random::{Rng, SeededRng};
+
+        let key = "YELLOW SUBMARINE".as_bytes().to_vec();
+        for seed in 0..100 {
+            let mut rng = SeededRng::new(seed);
+            let mut oracle = from_fn(|input: &Bytes| {
+                let len = rng.range(1, 64);
+                let mut plaintext = rng.bytes(len);
+                plaintext.extend(input);
+                plaintext.extend("a hidden suffix".as_bytes());
+                encrypt::aes_ecb(&plaintext, &key)
+            });
+
+            // Whatever else it finds, it can't pin the prefix down
+            if let Some(profile) = super::profile(&mut oracle) {
+                assert_eq!(profile.prefix_len, None, "seed {}", seed);
+                assert_eq!(profile.suffix_len, None, "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_profile_cbc() {
+        use openssl::symm::{encrypt, Cipher};
+
+        let mut oracle = from_fn(|input: &Bytes| {
+            let mut plaintext = input.clone();
+            plaintext.extend("a hidden suffix".as_bytes());
+            encrypt(Cipher::aes_128_cbc(), &[7; 16], Some(&[0; 16]), &plaintext)
+                .unwrap()
+        });
+
+        let profile = super::profile(&mut oracle).unwrap();
+        assert_eq!(profile.block_size, 16);
+        assert!(!profile.ecb);
+        assert_eq!(profile.prefix_len, None);
+        assert_eq!(profile.overhead, 15);
+    }
+
+    // A toy 8 byte block cipher so we're not tied to AES
+    fn toy_ecb_oracle(suffix: &'static str) -> impl EncryptionOracle {
+        use crate::padding::pkcs7;
+
+        let key = [0x3c, 0x91, 0x07, 0xee, 0x5a, 0x12, 0xc8, 0x66];
+        from_fn(move |input: &Bytes| {
+            let mut plaintext = input.clone();
+            plaintext.extend(suffix.as_bytes());
+            pkcs7(&plaintext, 8)
+                .chunks(8)
+                .flat_map(|block| {
+                    let mut out: Vec<u8> =
+                        block.iter().zip(&key).map(|(b, k)| b ^ k).collect();
+                    out.rotate_left(3);
+                    out
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn test_byte_at_a_time_block_size() {
+        let secret = "works for eight byte blocks too";
+        let mut oracle = toy_ecb_oracle(secret);
+
+        assert_eq!(profile(&mut oracle).unwrap().block_size, 8);
+        assert_eq!(
+            byte_at_a_time(&mut oracle, false),
+            Some(secret.as_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn test_byte_at_a_time_not_ecb() {
+        let mut oracle = from_fn(|input: &Bytes| input.clone());
+        assert_eq!(byte_at_a_time(&mut oracle, false), None);
+    }
+
+    #[test]
+    fn test_byte_at_a_time_random_prefix_gives_up() {
+        use openssl::symm::{encrypt, Cipher};
+
+        let mut cbc = from_fn(|input: &Bytes| {
+            encrypt(Cipher::aes_128_cbc(), &[7; 16], Some(&[0; 16]), input)
+                .unwrap()
+        });
+        assert_eq!(byte_at_a_time(&mut cbc, true), None);
+
+        // Looks like ECB, but our markers never show up
+        let mut ignores_input = from_fn(|_: &Bytes| vec![0; 128]);
+        assert_eq!(byte_at_a_time(&mut ignores_input, true), None);
+    }
+
+    #[test]
+    fn test_cut_and_paste_wide_blocks() {
+        // 256 byte blocks, which PKCS#7 has no padding byte for
+        let mut oracle = from_fn(|input: &Bytes| {
+            let mut plaintext = input.clone();
+            plaintext.extend("role=user".as_bytes());
+            let pad = 256 - plaintext.len() % 256;
+            plaintext.resize(plaintext.len() + pad, 0);
+            plaintext
+        });
+
+        assert_eq!(profile(&mut oracle).unwrap().block_size, 256);
+        assert_eq!(
+            cut_and_paste(&mut oracle, 4, &"admin".as_bytes().to_vec()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_profile_stream() {
+        let mut oracle = from_fn(|input: &Bytes| input.clone());
+        assert_eq!(super::profile(&mut oracle), None);
+    }
+}
@@ -0,0 +1,283 @@
+//
+// Attacks on linear congruential generators.
+//
+// Each output of a full LCG is its entire state, so one output is enough to
+// clone it if we know the parameters, and a handful is enough to work the
+// parameters out. When only the top bits come out, the rest can be guessed
+// and checked against the outputs that follow.
+//
+
+use crate::lcg::Lcg;
+
+// Don't try more than this many multipliers when the modulus leaves the
+// equation for `a` with more than one answer.
+const MAX_CANDIDATES: u64 = 1 << 16;
+
+// Give up on a modulus if the GCD is more than this many times too big.
+const MAX_COFACTOR: u128 = 1 << 16;
+
+//
+// Find the modulus of an LCG from consecutive full outputs.
+//
+// Differences between outputs go `t1 = a * t0` (mod m), so
+// `t2 * t0 - t1 * t1` is a multiple of m. The GCD of a few of those is m
+// times some small factor, which is often more than 1 when m itself has
+// small factors. So we divide the GCD by 1, 2, 3 and so on, and keep the
+// first quotient that `recover_params` can fit to every output. More outputs
+// make a wrong fit less likely, and at least six are needed.
+//
+// Returns `None` if no quotient above the largest output fits.
+//
+// Outputs near 2^64 would overflow an i128 here, so we take the size and sign
+// of each term separately. Every output is below 2^64, which keeps
+// `|t2 * t0| + t1 * t1` below 2^128 when the signs differ.
+//
+pub fn recover_modulus(outputs: &[u64]) -> Option<u64> {
+    if outputs.len() < 6 {
+        return None;
+    }
+
+    let diffs: Vec<i128> = outputs
+        .windows(2)
+        .map(|pair| pair[1] as i128 - pair[0] as i128)
+        .collect();
+
+    let m = diffs
+        .windows(3)
+        .map(|t| {
+            let product = t[0].unsigned_abs() * t[2].unsigned_abs();
+            let square = t[1].unsigned_abs() * t[1].unsigned_abs();
+
+            if (t[0] < 0) == (t[2] < 0) {
+                product.abs_diff(square)
+            } else {
+                product + square
+            }
+        })
+        .fold(0, gcd);
+    let max = *outputs.iter().max()? as u128;
+
+    (1..=MAX_COFACTOR)
+        .filter(|k| m.is_multiple_of(*k))
+        .map(|k| m / k)
+        .take_while(|candidate| *candidate > max)
+        .filter(|candidate| *candidate <= u64::MAX as u128)
+        .map(|candidate| candidate as u64)
+        .find(|candidate| recover_params(outputs, *candidate).is_some())
+}
+
+//
+// Find the multiplier and increment of an LCG with a known modulus from at
+// least three consecutive full outputs. Returns `(a, c)`.
+//
+// `s2 - s1 = a * (s1 - s0)` (mod m) gives `a`, though when the difference
+// shares factors with m there are several answers, and we keep the first
+// that predicts the rest of the outputs.
+//
+pub fn recover_params(outputs: &[u64], m: u64) -> Option<(u64, u64)> {
+    if outputs.len() < 3 || outputs.iter().any(|s| *s >= m) {
+        return None;
+    }
+
+    let m = m as u128;
+    let (s0, s1, s2) =
+        (outputs[0] as u128, outputs[1] as u128, outputs[2] as u128);
+    let d0 = (s1 + m - s0) % m;
+    let d1 = (s2 + m - s1) % m;
+
+    // Solve a * d0 = d1 (mod m), allowing for d0 sharing factors with m
+    let g = gcd(d0, m);
+    if !d1.is_multiple_of(g)
+        || m / g > u64::MAX as u128
+        || g > MAX_CANDIDATES as u128
+    {
+        return None;
+    }
+
+    let step = m / g;
+    let base = if step == 1 {
+        0
+    } else {
+        (d1 / g) * mod_inverse(d0 / g, step)? % step
+    };
+
+    (0..g).map(|k| base + k * step).find_map(|a| {
+        let c = (s1 + m - a * s0 % m) % m;
+        let predicts = outputs
+            .windows(2)
+            .all(|pair| (a * pair[0] as u128 + c) % m == pair[1] as u128);
+
+        if predicts {
+            Some((a as u64, c as u64))
+        } else {
+            None
+        }
+    })
+}
+
+//
+// Clone an LCG from consecutive full outputs, working out the modulus too if
+// it isn't given. The clone carries on from just after the last output.
+//
+pub fn clone(outputs: &[u64], m: Option<u64>) -> Option<Lcg> {
+    let m = match m {
+        Some(m) => m,
+        None => recover_modulus(outputs)?,
+    };
+    let (a, c) = recover_params(outputs, m)?;
+
+    Some(Lcg::new(a, c, m, 0, *outputs.last()?))
+}
+
+//
+// Clone an LCG with known parameters that only gives out the bits above
+// `shift`, by trying every value of the missing low bits of the first
+// output's state and keeping the one that predicts the rest.
+//
+// That's `2^shift` guesses, which is quick for Java's 16 missing bits and
+// slow much beyond 24. A couple of outputs is usually enough to leave one
+// answer, more makes sure.
+//
+pub fn clone_truncated(
+    outputs: &[u64],
+    a: u64,
+    c: u64,
+    m: u64,
+    shift: u32,
+) -> Option<Lcg> {
+    let (first, rest) = outputs.split_first()?;
+
+    (0..1u64 << shift)
+        .map(|low| first << shift | low)
+        .filter(|state| *state < m)
+        .find_map(|state| {
+            let mut rng = Lcg::new(a, c, m, shift, state);
+            if rest.iter().all(|output| rng.next_u64() == *output) {
+                Some(rng)
+            } else {
+                None
+            }
+        })
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// The inverse of `a` modulo `m` by the extended Euclidean algorithm, if
+// there is one.
+fn mod_inverse(a: u128, m: u128) -> Option<u128> {
+    let (mut old_r, mut r) = (a as i128, m as i128);
+    let (mut old_s, mut s) = (1i128, 0i128);
+
+    while r != 0 {
+        let q = old_r / r;
+        let next_r = old_r - q * r;
+        old_r = r;
+        r = next_r;
+        let next_s = old_s - q * s;
+        old_s = s;
+        s = next_s;
+    }
+
+    if old_r != 1 {
+        return None;
+    }
+    Some(old_s.rem_euclid(m as i128) as u128)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clone_known_modulus() {
+        let mut rng = Lcg::glibc(31337);
+        let outputs: Vec<u64> = rng.by_ref().take(4).collect();
+
+        assert_eq!(
+            recover_params(&outputs, 1 << 31),
+            Some((1_103_515_245, 12345))
+        );
+
+        let mut cloned = clone(&outputs, Some(1 << 31)).unwrap();
+        for _ in 0..100 {
+            assert_eq!(cloned.next_u64(), rng.next_u64());
+        }
+    }
+
+    #[test]
+    fn test_clone_unknown_modulus() {
+        // A prime modulus and made up parameters
+        let m = 1_099_511_627_689;
+        let mut rng = Lcg::new(
+            672_257_317_069_504_227,
+            7_382_843_889_490_547_368,
+            m,
+            0,
+            2_300_000_000_000,
+        );
+        let outputs: Vec<u64> = rng.by_ref().take(10).collect();
+
+        assert_eq!(recover_modulus(&outputs), Some(m));
+
+        let mut cloned = clone(&outputs, None).unwrap();
+        for _ in 0..100 {
+            assert_eq!(cloned.next_u64(), rng.next_u64());
+        }
+
+        assert_eq!(recover_modulus(&outputs[..5]), None);
+    }
+
+    #[test]
+    fn test_clone_modulus_near_u64_max() {
+        // The largest prime below 2^64, and outputs far enough apart that
+        // the differences multiply past 2^127
+        let m = 18_446_744_073_709_551_557;
+        let mut rng =
+            Lcg::new(0xD1B5_4A32_D192_ED03, 0x9E37_79B9_7F4A_7C15, m, 0, 3);
+        let outputs: Vec<u64> = rng.by_ref().take(10).collect();
+
+        assert_eq!(recover_modulus(&outputs), Some(m));
+
+        let mut cloned = clone(&outputs, None).unwrap();
+        for _ in 0..100 {
+            assert_eq!(cloned.next_u64(), rng.next_u64());
+        }
+    }
+
+    #[test]
+    fn test_clone_composite_modulus() {
+        // 900 is 2^2 * 3^2 * 5^2, and factors like those tend to be left
+        // over in the GCD
+        let m = 900 * 1_000_000_007;
+        for seed in 1..20 {
+            let mut rng = Lcg::new(123_456_789_012, 987_654_321, m, 0, seed);
+            let outputs: Vec<u64> = rng.by_ref().take(10).collect();
+
+            assert_eq!(recover_modulus(&outputs), Some(m));
+
+            let mut cloned = clone(&outputs, None).unwrap();
+            for _ in 0..100 {
+                assert_eq!(cloned.next_u64(), rng.next_u64());
+            }
+        }
+    }
+
+    #[test]
+    fn test_clone_truncated() {
+        let mut rng = Lcg::java(123_456_789);
+        let outputs: Vec<u64> = rng.by_ref().take(3).collect();
+
+        let mut cloned =
+            clone_truncated(&outputs, 0x5_DEEC_E66D, 0xB, 1 << 48, 16).unwrap();
+        assert_eq!(cloned.state(), rng.state());
+        for _ in 0..100 {
+            assert_eq!(cloned.next_u64(), rng.next_u64());
+        }
+    }
+}
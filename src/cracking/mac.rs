@@ -0,0 +1,154 @@
+//
+// Forging secret-prefix MACs by length extension.
+//
+// A secret-prefix MAC is the hash of `key || message`, and that digest is
+// the hash's state after the message and its padding. Starting from it, we
+// can hash more on the end and get the MAC of
+// `key || message || padding || suffix` without ever seeing the key. All
+// we need is the key's length for the padding, which we guess.
+//
+
+use super::oracle::{MacOracle, SignedMessage};
+use crate::md4::Md4Core;
+use crate::merkle_damgard::{self, Compression, Hasher};
+use crate::sha1::Sha1Core;
+use crate::sha2::{Sha256Core, Sha512Core};
+use crate::Bytes;
+use std::ops::RangeInclusive;
+
+//
+// Extend a secret-prefix MAC over any Merkle-Damgård hash with `suffix`,
+// trying each key length in turn until the verifier accepts. Returns the
+// forged message and its MAC.
+//
+pub fn length_extension<C: Compression>(
+    verifier: &mut impl MacOracle,
+    message: &[u8],
+    mac: &[u8],
+    suffix: &[u8],
+    key_lengths: RangeInclusive<usize>,
+) -> Option<(Bytes, Bytes)> {
+    key_lengths.into_iter().find_map(|key_len| {
+        let hashed = (key_len + message.len()) as u64;
+        let glue = merkle_damgard::glue_padding::<C>(hashed);
+
+        let mut hasher =
+            Hasher::<C>::from_digest(mac, hashed + glue.len() as u64)?;
+        hasher.update(suffix);
+
+        let signed = SignedMessage {
+            message: [message, &glue, suffix].concat(),
+            mac: hasher.finalize(),
+        };
+
+        if verifier.query(&signed) {
+            Some((signed.message, signed.mac))
+        } else {
+            None
+        }
+    })
+}
+
+pub fn sha1_length_extension(
+    verifier: &mut impl MacOracle,
+    message: &[u8],
+    mac: &[u8],
+    suffix: &[u8],
+    key_lengths: RangeInclusive<usize>,
+) -> Option<(Bytes, Bytes)> {
+    length_extension::<Sha1Core>(verifier, message, mac, suffix, key_lengths)
+}
+
+pub fn md4_length_extension(
+    verifier: &mut impl MacOracle,
+    message: &[u8],
+    mac: &[u8],
+    suffix: &[u8],
+    key_lengths: RangeInclusive<usize>,
+) -> Option<(Bytes, Bytes)> {
+    length_extension::<Md4Core>(verifier, message, mac, suffix, key_lengths)
+}
+
+pub fn sha256_length_extension(
+    verifier: &mut impl MacOracle,
+    message: &[u8],
+    mac: &[u8],
+    suffix: &[u8],
+    key_lengths: RangeInclusive<usize>,
+) -> Option<(Bytes, Bytes)> {
+    length_extension::<Sha256Core>(verifier, message, mac, suffix, key_lengths)
+}
+
+pub fn sha512_length_extension(
+    verifier: &mut impl MacOracle,
+    message: &[u8],
+    mac: &[u8],
+    suffix: &[u8],
+    key_lengths: RangeInclusive<usize>,
+) -> Option<(Bytes, Bytes)> {
+    length_extension::<Sha512Core>(verifier, message, mac, suffix, key_lengths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cracking::oracle::{self, Oracle};
+    use crate::mac::{secret_prefix_mac, verify_secret_prefix_mac};
+    use crate::random::OsRng;
+    use crate::sha2::{sha256, sha512};
+    use crate::webapp::MacService;
+
+    #[test]
+    fn test_sha1_length_extension() {
+        let key = b"fourteen bytes";
+        let message = b"user=guest";
+        let mac = secret_prefix_mac(key, message);
+
+        let mut verifier = oracle::from_fn(|signed: &SignedMessage| {
+            verify_secret_prefix_mac(key, &signed.message, &signed.mac)
+        })
+        .counted();
+        let (forged, forged_mac) = sha1_length_extension(
+            &mut verifier,
+            message,
+            &mac,
+            b"&user=admin",
+            0..=32,
+        )
+        .unwrap();
+
+        assert!(forged.starts_with(message));
+        assert!(forged.ends_with(b"&user=admin"));
+        assert_eq!(forged_mac, secret_prefix_mac(key, &forged));
+        assert_eq!(verifier.queries(), key.len() + 1);
+    }
+
+    #[test]
+    fn test_sha2_length_extension() {
+        let mut service = MacService::with_hash(&mut OsRng, sha256);
+        let (message, mac) = service.signed_comment();
+        let (forged, forged_mac) = sha256_length_extension(
+            &mut service,
+            &message,
+            &mac,
+            b";admin=true",
+            0..=64,
+        )
+        .unwrap();
+        assert!(service.is_admin(&forged, &forged_mac));
+
+        // SHA-512's padding is a different size, with a 16 byte length
+        let mut service = MacService::with_hash(&mut OsRng, sha512);
+        let (message, mac) = service.signed_comment();
+        let (forged, forged_mac) = sha512_length_extension(
+            &mut service,
+            &message,
+            &mac,
+            b";admin=true",
+            0..=64,
+        )
+        .unwrap();
+        assert!(service.is_admin(&forged, &forged_mac));
+        assert!(forged.starts_with(&message));
+    }
+}
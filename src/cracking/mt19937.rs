@@ -0,0 +1,458 @@
+//
+// Attacks on the MT19937 Mersenne Twister.
+//
+// Each output is one word of state put through an invertible transform, so
+// 624 outputs in a row give away the entire state. With fewer, or with only
+// some bits of each, we can still work backwards: every operation in the
+// generator is XORs and shifts, so each output bit is a fixed XOR of bits
+// of the starting state. Collect enough of those equations and solve them.
+//
+
+use crate::mt19937::{self, Mt19937, M, MATRIX_A, N};
+use crate::Bytes;
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+// Unknowns in the linear system: 624 words of 32 bits
+const VARS: usize = N * 32;
+const ROW_WORDS: usize = VARS / 64;
+
+// The generator only ever uses the top bit of the oldest word again, so 31
+// bits of it can't be pinned down and don't need to be.
+const NEEDED_RANK: usize = VARS - 31;
+
+//
+// Clone a generator from 624 or more consecutive outputs. The clone carries
+// on from just after the last one.
+//
+pub fn clone(outputs: &[u32]) -> Option<Mt19937> {
+    if outputs.len() < N {
+        return None;
+    }
+
+    let mut state = [0; N];
+    for (word, output) in state.iter_mut().zip(&outputs[outputs.len() - N..]) {
+        *word = mt19937::untemper(*output);
+    }
+
+    Some(Mt19937::from_state(state))
+}
+
+//
+// Clone a generator from scattered or partial outputs.
+//
+// Each observation is `(index, mask, value)`: the output at `index`
+// (counting from the first one observed) had the bits set in `mask` equal
+// to those in `value`. Indexes can skip around, and masks can cover as
+// little as a single bit, as long as there are enough known bits overall.
+// Around 20,000 well spread bits is the minimum.
+//
+// Returns a generator that carries on from just after the highest index, or
+// `None` if the observations don't pin down the state.
+//
+pub fn clone_from_bits(observations: &[(usize, u32, u32)]) -> Option<Mt19937> {
+    let mut sorted = observations.to_vec();
+    sorted.sort_by_key(|(index, _, _)| *index);
+    let last = sorted.last()?.0;
+
+    let temper = temper_matrix();
+    let mut system = System::new();
+    let mut window = SymbolicState::new();
+
+    for (index, mask, value) in sorted {
+        let word = window.word(index);
+
+        for (bit, inputs) in temper.iter().enumerate() {
+            if mask >> bit & 1 == 0 {
+                continue;
+            }
+
+            let mut row = vec![0; ROW_WORDS];
+            for (input, symbolic) in word.iter().enumerate() {
+                if inputs >> input & 1 == 1 {
+                    xor_into(&mut row, symbolic);
+                }
+            }
+            system.add(row, value >> bit & 1 == 1);
+        }
+    }
+
+    if system.rank() < NEEDED_RANK {
+        return None;
+    }
+
+    let solution = system.solve();
+    let mut state = [0u32; N];
+    for (i, word) in state.iter_mut().enumerate() {
+        for bit in 0..32 {
+            let var = i * 32 + bit;
+            if solution[var / 64] >> (var % 64) & 1 == 1 {
+                *word |= 1 << bit;
+            }
+        }
+    }
+
+    // Run the recovered state forward until it has just output `last`
+    let mut rng = Mt19937::from_state(state);
+    let mut recent: VecDeque<u32> = state.iter().cloned().collect();
+    for _ in N..=last {
+        recent.pop_front();
+        recent.push_back(mt19937::untemper(rng.next_u32()));
+    }
+
+    let mut state = [0; N];
+    for (word, value) in state.iter_mut().zip(recent.iter()) {
+        *word = *value;
+    }
+    Some(Mt19937::from_state(state))
+}
+
+//
+// Try every seed in `seeds` until `matches` accepts the generator it makes.
+//
+// The range is split across one thread per core, and they all stop as soon
+// as any of them finds a match. If more than one seed matches, there's no
+// telling which one comes back.
+//
+pub fn find_seed<F>(seeds: RangeInclusive<u32>, matches: F) -> Option<u32>
+where
+    F: Fn(&mut Mt19937) -> bool + Sync,
+{
+    let (start, end) = (*seeds.start() as u64, *seeds.end() as u64);
+    if start > end {
+        return None;
+    }
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = (end - start + 1).div_ceil(threads as u64);
+    let found = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads as u64)
+            .map(|i| start + i * chunk)
+            .filter(|first| *first <= end)
+            .map(|first| {
+                let last = (first + chunk - 1).min(end);
+                let (found, matches) = (&found, &matches);
+
+                scope.spawn(move || {
+                    for seed in first..=last {
+                        if found.load(Ordering::Relaxed) {
+                            return None;
+                        }
+                        if matches(&mut Mt19937::new(seed as u32)) {
+                            found.store(true, Ordering::Relaxed);
+                            return Some(seed as u32);
+                        }
+                    }
+                    None
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().unwrap())
+            .min()
+    })
+}
+
+// Find the seed whose first output was `output`.
+pub fn seed_from_output(
+    output: u32,
+    seeds: RangeInclusive<u32>,
+) -> Option<u32> {
+    find_seed(seeds, |rng| rng.next_u32() == output)
+}
+
+//
+// Find the timestamp a generator was seeded with, given its first output,
+// by trying every second from `now - window` up to `now`.
+//
+pub fn timestamp_seed(output: u32, now: u32, window: u32) -> Option<u32> {
+    seed_from_output(output, now.saturating_sub(window)..=now)
+}
+
+//
+// Check whether a token came from a generator seeded with a timestamp in
+// the last `window` seconds, by regenerating it from each of them. Tokens
+// are the low bytes of the first outputs, as from `Mt19937::bytes`.
+//
+// Returns the timestamp it was seeded with.
+//
+pub fn token_timestamp(token: &Bytes, now: u32, window: u32) -> Option<u32> {
+    find_seed(now.saturating_sub(window)..=now, |rng| {
+        rng.bytes(token.len()) == *token
+    })
+}
+
+//
+// Find the 16 bit seed of an `encrypt::mt19937_cipher` ciphertext whose
+// plaintext is known to end in `suffix`. XORing the end of the ciphertext
+// with the suffix gives the end of the keystream to check each seed against.
+//
+pub fn cipher_seed(ciphertext: &Bytes, suffix: &Bytes) -> Option<u16> {
+    let start = ciphertext.len().checked_sub(suffix.len())?;
+    let keystream: Bytes = ciphertext[start..]
+        .iter()
+        .zip(suffix)
+        .map(|(c, p)| c ^ p)
+        .collect();
+
+    let seed = find_seed(0..=0xFFFF, |rng| {
+        rng.bytes(ciphertext.len())[start..] == keystream[..]
+    })?;
+    Some(seed as u16)
+}
+
+// For each output bit of the tempering transform, which input bits are
+// XORed together to make it.
+fn temper_matrix() -> [u32; 32] {
+    let mut matrix = [0; 32];
+    for input in 0..32 {
+        let output = mt19937::temper(1 << input);
+        for (bit, inputs) in matrix.iter_mut().enumerate() {
+            if output >> bit & 1 == 1 {
+                *inputs |= 1 << input;
+            }
+        }
+    }
+    matrix
+}
+
+fn xor_into(row: &mut [u64], other: &[u64]) {
+    for (a, b) in row.iter_mut().zip(other) {
+        *a ^= b;
+    }
+}
+
+// A word of generator state, with each bit written as the set of starting
+// state bits XORed together to make it.
+type SymbolicWord = Vec<Vec<u64>>;
+
+//
+// The generator's state words as XORs of the unknowns.
+//
+// Word `k` is the state behind output `k`. The first 624 are the unknowns
+// themselves, and after that each word follows from three earlier ones the
+// same way twisting computes it. We only keep the last 624 around.
+//
+struct SymbolicState {
+    words: VecDeque<SymbolicWord>,
+    next: usize,
+}
+
+impl SymbolicState {
+    fn new() -> SymbolicState {
+        let words = (0..N)
+            .map(|i| {
+                (0..32)
+                    .map(|bit| {
+                        let var = i * 32 + bit;
+                        let mut row = vec![0; ROW_WORDS];
+                        row[var / 64] = 1 << (var % 64);
+                        row
+                    })
+                    .collect()
+            })
+            .collect();
+
+        SymbolicState { words, next: N }
+    }
+
+    fn word(&mut self, index: usize) -> &SymbolicWord {
+        while self.next <= index {
+            let twisted = self.twist();
+            self.words.pop_front();
+            self.words.push_back(twisted);
+            self.next += 1;
+        }
+
+        &self.words[self.words.len() - (self.next - index)]
+    }
+
+    fn twist(&self) -> SymbolicWord {
+        let old = &self.words[0];
+        let following = &self.words[1];
+        let mixed = &self.words[M];
+
+        (0..32)
+            .map(|bit| {
+                let mut row = mixed[bit].clone();
+
+                // y takes the top bit from `old` and the rest from
+                // `following`, and is shifted down one
+                if bit == 30 {
+                    xor_into(&mut row, &old[31]);
+                } else if bit < 30 {
+                    xor_into(&mut row, &following[bit + 1]);
+                }
+
+                if MATRIX_A >> bit & 1 == 1 {
+                    xor_into(&mut row, &following[0]);
+                }
+                row
+            })
+            .collect()
+    }
+}
+
+//
+// Linear equations over GF(2), kept in echelon form as they're added. Each
+// row is stored under its lowest unknown, and every row stored below that
+// has already been cleared out of it.
+//
+struct System {
+    rows: Vec<Option<(Vec<u64>, bool)>>,
+    rank: usize,
+}
+
+impl System {
+    fn new() -> System {
+        System {
+            rows: vec![None; VARS],
+            rank: 0,
+        }
+    }
+
+    fn rank(&self) -> usize {
+        self.rank
+    }
+
+    fn add(&mut self, mut row: Vec<u64>, mut value: bool) {
+        let mut start = 0;
+
+        while let Some(pivot) = lowest_bit(&row, start) {
+            match &self.rows[pivot] {
+                Some((other, other_value)) => {
+                    let from = pivot / 64;
+                    xor_into(&mut row[from..], &other[from..]);
+                    value ^= other_value;
+                    start = from;
+                }
+                None => {
+                    self.rows[pivot] = Some((row, value));
+                    self.rank += 1;
+                    return;
+                }
+            }
+        }
+    }
+
+    // Back substitute from the highest unknown down, leaving any unknowns
+    // without a row as zero.
+    fn solve(&self) -> Vec<u64> {
+        let mut solution = vec![0u64; ROW_WORDS];
+
+        for var in (0..VARS).rev() {
+            if let Some((row, value)) = &self.rows[var] {
+                let mut bit = *value;
+                for (a, b) in row.iter().zip(solution.iter()).skip(var / 64) {
+                    bit ^= (a & b).count_ones() % 2 == 1;
+                }
+                if bit {
+                    solution[var / 64] |= 1 << (var % 64);
+                }
+            }
+        }
+
+        solution
+    }
+}
+
+fn lowest_bit(row: &[u64], start: usize) -> Option<usize> {
+    row.iter()
+        .enumerate()
+        .skip(start)
+        .find(|(_, word)| **word != 0)
+        .map(|(i, word)| i * 64 + word.trailing_zeros() as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clone() {
+        let mut rng = Mt19937::new(0xC0FFEE);
+        let outputs: Vec<u32> = rng.by_ref().take(700).collect();
+
+        let mut cloned = clone(&outputs).unwrap();
+        for _ in 0..2000 {
+            assert_eq!(cloned.next_u32(), rng.next_u32());
+        }
+
+        assert!(clone(&outputs[..623]).is_none());
+    }
+
+    #[test]
+    fn test_find_seed() {
+        let output = Mt19937::new(40_000).next_u32();
+        assert_eq!(seed_from_output(output, 0..=0xFFFF), Some(40_000));
+        assert_eq!(seed_from_output(output, 0..=39_999), None);
+        assert_eq!(seed_from_output(output, 40_000..=40_000), Some(40_000));
+
+        let mut rng = Mt19937::new(u32::MAX);
+        rng.next_u32();
+        let second = rng.next_u32();
+        let found = find_seed(u32::MAX - 100..=u32::MAX, |rng| {
+            rng.next_u32();
+            rng.next_u32() == second
+        });
+        assert_eq!(found, Some(u32::MAX));
+    }
+
+    #[test]
+    fn test_token_timestamp() {
+        let now = 1_700_000_000;
+        let token = Mt19937::new(now - 300).bytes(16);
+
+        assert_eq!(token_timestamp(&token, now, 3600), Some(now - 300));
+        assert_eq!(token_timestamp(&token, now, 60), None);
+        assert_eq!(token_timestamp(&vec![0x42; 16], now, 3600), None);
+    }
+
+    #[test]
+    fn test_clone_from_bits() {
+        let mut rng = Mt19937::new(1234);
+        for _ in 0..100 {
+            rng.next_u32();
+        }
+        let outputs: Vec<u32> = rng.by_ref().take(1400).collect();
+
+        // Only the top 16 bits of each output, like `rand() >> 16`
+        let observations: Vec<(usize, u32, u32)> = outputs
+            .iter()
+            .enumerate()
+            .map(|(i, output)| (i, 0xFFFF_0000, *output))
+            .collect();
+
+        let mut cloned = clone_from_bits(&observations).unwrap();
+        for _ in 0..1000 {
+            assert_eq!(cloned.next_u32(), rng.next_u32());
+        }
+
+        assert!(clone_from_bits(&observations[..600]).is_none());
+    }
+
+    #[test]
+    fn test_clone_from_bits_gap() {
+        let mut rng = Mt19937::new(99);
+        let outputs: Vec<u32> = rng.by_ref().take(1300).collect();
+
+        // Everything but a stretch in the middle we never saw
+        let observations: Vec<(usize, u32, u32)> = outputs
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i < 400 || *i >= 900)
+            .map(|(i, output)| (i, 0xFFFF_FFFF, *output))
+            .collect();
+
+        let mut cloned = clone_from_bits(&observations).unwrap();
+        for _ in 0..1000 {
+            assert_eq!(cloned.next_u32(), rng.next_u32());
+        }
+    }
+}
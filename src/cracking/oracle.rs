@@ -0,0 +1,256 @@
+//
+// Oracles for chosen-plaintext and chosen-ciphertext attacks.
+//
+// An oracle is anything the attacker can send input to and get an answer
+// back from, like a server that encrypts whatever we give it or one that
+// only tells us whether our ciphertext had valid padding. Attacks are
+// written against these traits so we can wrap the oracle to count queries,
+// cap how many we allow, or record everything that was asked.
+//
+
+use crate::Bytes;
+use std::marker::PhantomData;
+
+pub trait Oracle {
+    type Input;
+    type Output;
+
+    fn query(&mut self, input: &Self::Input) -> Self::Output;
+
+    /// Count every query made through the wrapper.
+    fn counted(self) -> Counted<Self>
+    where
+        Self: Sized,
+    {
+        Counted {
+            inner: self,
+            queries: 0,
+        }
+    }
+
+    /// Panic once more than `budget` queries have been made.
+    fn budgeted(self, budget: usize) -> Budgeted<Self>
+    where
+        Self: Sized,
+    {
+        Budgeted {
+            inner: self,
+            remaining: budget,
+        }
+    }
+
+    /// Keep every input and output pair in order.
+    fn recorded(self) -> Recorded<Self>
+    where
+        Self: Sized,
+        Self::Input: Clone,
+        Self::Output: Clone,
+    {
+        Recorded {
+            inner: self,
+            transcript: Vec::new(),
+        }
+    }
+}
+
+/// Takes plaintext and hands back ciphertext.
+pub trait EncryptionOracle: Oracle<Input = Bytes, Output = Bytes> {}
+
+impl<T: Oracle<Input = Bytes, Output = Bytes>> EncryptionOracle for T {}
+
+/// Takes an `(iv, ciphertext)` pair and says whether it decrypts to
+/// correctly padded plaintext.
+pub trait PaddingOracle: Oracle<Input = (Bytes, Bytes), Output = bool> {}
+
+impl<T: Oracle<Input = (Bytes, Bytes), Output = bool>> PaddingOracle for T {}
+
+/// A message along with the MAC claimed for it. It has a type of its own so
+/// a padding oracle, which also takes two byte strings, can't pass for a
+/// MAC verifier.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignedMessage {
+    pub message: Bytes,
+    pub mac: Bytes,
+}
+
+/// Takes a signed message and says whether its MAC is valid.
+pub trait MacOracle: Oracle<Input = SignedMessage, Output = bool> {}
+
+impl<T: Oracle<Input = SignedMessage, Output = bool>> MacOracle for T {}
+
+/// Takes a `(ciphertext, offset, newtext)` triple and hands back the
+/// ciphertext with `newtext` encrypted in at `offset`.
+pub trait EditOracle:
+    Oracle<Input = (Bytes, usize, Bytes), Output = Bytes>
+{
+}
+
+impl<T: Oracle<Input = (Bytes, usize, Bytes), Output = Bytes>> EditOracle
+    for T
+{
+}
+
+impl<O: Oracle + ?Sized> Oracle for &mut O {
+    type Input = O::Input;
+    type Output = O::Output;
+
+    fn query(&mut self, input: &Self::Input) -> Self::Output {
+        (**self).query(input)
+    }
+}
+
+/// An oracle backed by a closure. Build one with `from_fn`.
+pub struct FnOracle<I, O, F> {
+    f: F,
+    marker: PhantomData<fn(&I) -> O>,
+}
+
+pub fn from_fn<I, O, F: FnMut(&I) -> O>(f: F) -> FnOracle<I, O, F> {
+    FnOracle {
+        f,
+        marker: PhantomData,
+    }
+}
+
+impl<I, O, F: FnMut(&I) -> O> Oracle for FnOracle<I, O, F> {
+    type Input = I;
+    type Output = O;
+
+    fn query(&mut self, input: &I) -> O {
+        (self.f)(input)
+    }
+}
+
+pub struct Counted<O> {
+    inner: O,
+    queries: usize,
+}
+
+impl<O> Counted<O> {
+    pub fn queries(&self) -> usize {
+        self.queries
+    }
+
+    pub fn into_inner(self) -> O {
+        self.inner
+    }
+}
+
+impl<O: Oracle> Oracle for Counted<O> {
+    type Input = O::Input;
+    type Output = O::Output;
+
+    fn query(&mut self, input: &Self::Input) -> Self::Output {
+        self.queries += 1;
+        self.inner.query(input)
+    }
+}
+
+pub struct Budgeted<O> {
+    inner: O,
+    remaining: usize,
+}
+
+impl<O> Budgeted<O> {
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    pub fn into_inner(self) -> O {
+        self.inner
+    }
+}
+
+impl<O: Oracle> Oracle for Budgeted<O> {
+    type Input = O::Input;
+    type Output = O::Output;
+
+    fn query(&mut self, input: &Self::Input) -> Self::Output {
+        if self.remaining == 0 {
+            panic!("oracle query budget exhausted");
+        }
+
+        self.remaining -= 1;
+        self.inner.query(input)
+    }
+}
+
+pub struct Recorded<O: Oracle> {
+    inner: O,
+    transcript: Vec<(O::Input, O::Output)>,
+}
+
+impl<O: Oracle> Recorded<O> {
+    pub fn transcript(&self) -> &[(O::Input, O::Output)] {
+        &self.transcript
+    }
+
+    pub fn into_inner(self) -> O {
+        self.inner
+    }
+}
+
+impl<O: Oracle> Oracle for Recorded<O>
+where
+    O::Input: Clone,
+    O::Output: Clone,
+{
+    type Input = O::Input;
+    type Output = O::Output;
+
+    fn query(&mut self, input: &Self::Input) -> Self::Output {
+        let output = self.inner.query(input);
+        self.transcript.push((input.clone(), output.clone()));
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reverser() -> impl EncryptionOracle {
+        from_fn(|input: &Bytes| input.iter().rev().cloned().collect())
+    }
+
+    #[test]
+    fn test_counted() {
+        let mut oracle = reverser().counted();
+        assert_eq!(oracle.query(&vec![1, 2, 3]), vec![3, 2, 1]);
+
+        // Attacks borrow the oracle so the count is still readable after
+        fn attack(mut oracle: impl EncryptionOracle) {
+            for _ in 0..4 {
+                oracle.query(&vec![0]);
+            }
+        }
+        attack(&mut oracle);
+
+        assert_eq!(oracle.queries(), 5);
+    }
+
+    #[test]
+    fn test_recorded() {
+        let mut oracle = reverser().counted().recorded();
+        oracle.query(&vec![1, 2]);
+        oracle.query(&vec![]);
+
+        assert_eq!(
+            oracle.transcript(),
+            &[(vec![1, 2], vec![2, 1]), (vec![], vec![])]
+        );
+        assert_eq!(oracle.into_inner().queries(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "budget exhausted")]
+    fn test_budgeted() {
+        let mut oracle = reverser().budgeted(3);
+        for _ in 0..3 {
+            oracle.query(&vec![0]);
+        }
+        assert_eq!(oracle.remaining(), 0);
+
+        oracle.query(&vec![0]);
+    }
+}
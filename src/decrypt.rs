@@ -1,11 +1,47 @@
-use crate::Bytes;
-use openssl::symm::{decrypt, Cipher};
+use crate::{encrypt, padding, transform, Bytes};
+use openssl::symm::{decrypt, Cipher, Crypter, Mode};
+
+pub fn mt19937_cipher(input: &Bytes, seed: u16) -> Bytes {
+    encrypt::mt19937_cipher(input, seed)
+}
 
 pub fn aes_ecb(input: &Bytes, key: &Bytes) -> Bytes {
     let cipher = Cipher::aes_128_ecb();
-    let result = decrypt(cipher, key, None, input);
-    match result {
-        Ok(bytes) => bytes,
-        Err(_) => Bytes::new(),
+    decrypt(cipher, key, None, input).unwrap_or_default()
+}
+
+pub fn aes_cbc(input: &Bytes, key: &Bytes, iv: &Bytes) -> Bytes {
+    padding::strip_pkcs7(&aes_cbc_padded(input, key, iv), 16)
+        .unwrap_or_default()
+}
+
+// Decrypt CBC but leave the padding on, for when it needs checking.
+pub fn aes_cbc_padded(input: &Bytes, key: &Bytes, iv: &Bytes) -> Bytes {
+    let mut prev = iv.clone();
+    let mut out = Bytes::new();
+
+    for block in input.chunks_exact(16) {
+        let block = block.to_vec();
+        out.extend(transform::byte_xor(&aes_block(&block, key), &prev));
+        prev = block;
     }
+
+    out
+}
+
+// Decrypt a single 16 byte block with no padding.
+pub fn aes_block(block: &Bytes, key: &Bytes) -> Bytes {
+    let mut crypter =
+        Crypter::new(Cipher::aes_128_ecb(), Mode::Decrypt, key, None).unwrap();
+    crypter.pad(false);
+
+    let mut out = vec![0; block.len() + 16];
+    let count = crypter.update(block, &mut out).unwrap();
+    out.truncate(count);
+    out
+}
+
+// CTR is its own inverse.
+pub fn aes_ctr(input: &Bytes, key: &Bytes, nonce: u64) -> Bytes {
+    encrypt::aes_ctr(input, key, nonce)
 }
@@ -1,5 +1,5 @@
 use crate::Bytes;
-use std::{char, u8};
+use std::char;
 
 pub fn hex_to_bytes(input: &str) -> Bytes {
     input
@@ -53,18 +53,18 @@ pub fn bytes_to_b64(input: Bytes) -> String {
             2 => {
                 out[j] |= (byte & 0xC0) >> 6;
                 out[j + 1] = byte & 0x3F;
-                j = j + 1;
+                j += 1;
             }
             _ => {}
         }
-        j = j + 1;
+        j += 1;
     }
 
     out.iter().map(|ind| b64[*ind as usize] as char).collect()
 }
 
 pub fn b64_to_bytes(input: &str) -> Bytes {
-    assert!(input.len() % 4 == 0);
+    assert!(input.len().is_multiple_of(4));
     let b64: Vec<u8> = (0x41..0x5B)
         .chain(0x61..0x7B)
         .chain(0x30..0x3A)
@@ -93,9 +93,9 @@ pub fn b64_to_bytes(input: &str) -> Bytes {
         remshift -= 1;
 
         if i % 4 == 3 {
-            out.push((buf >> 16) as u8 & 0xFF);
-            out.push((buf >> 8) as u8 & 0xFF);
-            out.push(buf as u8 & 0xFF);
+            out.push((buf >> 16) as u8);
+            out.push((buf >> 8) as u8);
+            out.push(buf as u8);
             buf = 0;
             remshift = 4;
         }
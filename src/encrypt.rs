@@ -1,15 +1,122 @@
-use crate::Bytes;
+use crate::mt19937::Mt19937;
+use crate::{padding, transform, Bytes};
+use openssl::symm::{encrypt, Cipher, Crypter, Mode};
 
 pub fn repeating_key_cipher(input: &Bytes, key: &Bytes) -> Bytes {
     let keylen = key.len();
-    let msglen = input.len();
     let mut out = Bytes::new();
 
-    for i in 0..msglen {
+    for (i, byte) in input.iter().enumerate() {
         let j = i % keylen;
 
-        out.push(input[i] ^ key[j]);
+        out.push(byte ^ key[j]);
     }
 
     out
 }
+
+//
+// A stream cipher with MT19937 as the keystream, one byte per output. The
+// seed is only 16 bits, which is the problem.
+//
+pub fn mt19937_cipher(input: &Bytes, seed: u16) -> Bytes {
+    let keystream = Mt19937::new(seed as u32).bytes(input.len());
+    transform::byte_xor(input, &keystream)
+}
+
+pub fn aes_ecb(input: &Bytes, key: &Bytes) -> Bytes {
+    let cipher = Cipher::aes_128_ecb();
+    encrypt(cipher, key, None, input).unwrap_or_default()
+}
+
+//
+// CBC mixes each plaintext block with the previous ciphertext block (or the
+// IV for the first one) before encrypting it.
+//
+pub fn aes_cbc(input: &Bytes, key: &Bytes, iv: &Bytes) -> Bytes {
+    let mut prev = iv.clone();
+    let mut out = Bytes::new();
+
+    for block in padding::pkcs7(input, 16).chunks(16) {
+        prev = aes_block(&transform::byte_xor(&block.to_vec(), &prev), key);
+        out.extend(&prev);
+    }
+
+    out
+}
+
+// Encrypt a single 16 byte block with no padding.
+pub fn aes_block(block: &Bytes, key: &Bytes) -> Bytes {
+    let mut crypter =
+        Crypter::new(Cipher::aes_128_ecb(), Mode::Encrypt, key, None).unwrap();
+    crypter.pad(false);
+
+    let mut out = vec![0; block.len() + 16];
+    let count = crypter.update(block, &mut out).unwrap();
+    out.truncate(count);
+    out
+}
+
+//
+// CTR turns AES into a stream cipher by encrypting a counter. Each block of
+// keystream is the encryption of the nonce and block number, both as 64 bit
+// little endian integers.
+//
+pub fn aes_ctr(input: &Bytes, key: &Bytes, nonce: u64) -> Bytes {
+    aes_ctr_at(input, key, nonce, 0)
+}
+
+// Encrypt as if `input` sat `offset` bytes into the stream.
+pub fn aes_ctr_at(
+    input: &Bytes,
+    key: &Bytes,
+    nonce: u64,
+    offset: usize,
+) -> Bytes {
+    let mut out = Bytes::new();
+    let mut keystream = Bytes::new();
+
+    for (i, byte) in input.iter().enumerate() {
+        let position = offset + i;
+        if i == 0 || position.is_multiple_of(16) {
+            keystream = ctr_keystream(key, nonce, (position / 16) as u64);
+        }
+
+        out.push(byte ^ keystream[position % 16]);
+    }
+
+    out
+}
+
+// One block of CTR keystream.
+fn ctr_keystream(key: &Bytes, nonce: u64, counter: u64) -> Bytes {
+    let mut state = nonce.to_le_bytes().to_vec();
+    state.extend(&counter.to_le_bytes());
+    aes_block(&state, key)
+}
+
+//
+// Overwrite part of a CTR ciphertext with new plaintext, re-encrypting only
+// the bytes that change. The ciphertext grows if the new text runs past its
+// end, and any gap before the offset is filled with encrypted zeros.
+//
+pub fn aes_ctr_edit(
+    ciphertext: &Bytes,
+    key: &Bytes,
+    nonce: u64,
+    offset: usize,
+    newtext: &Bytes,
+) -> Bytes {
+    let mut out = ciphertext.clone();
+    if out.len() < offset {
+        let gap = vec![0; offset - out.len()];
+        out.extend(aes_ctr_at(&gap, key, nonce, out.len()));
+    }
+    if out.len() < offset + newtext.len() {
+        out.resize(offset + newtext.len(), 0);
+    }
+
+    let edited = aes_ctr_at(newtext, key, nonce, offset);
+    out[offset..offset + edited.len()].copy_from_slice(&edited);
+    out
+}
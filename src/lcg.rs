@@ -0,0 +1,83 @@
+//
+// Linear congruential generators
+//
+// https://en.wikipedia.org/wiki/Linear_congruential_generator
+//
+// The whole state is one number, and each step is `state = a * state + c`
+// modulo `m`. Plenty of generators hand out only the top bits of the state,
+// which is what `shift` is for.
+//
+
+pub struct Lcg {
+    pub a: u64,
+    pub c: u64,
+    pub m: u64,
+    pub shift: u32,
+    state: u64,
+}
+
+impl Lcg {
+    pub fn new(a: u64, c: u64, m: u64, shift: u32, seed: u64) -> Lcg {
+        assert!(m > 1);
+
+        Lcg {
+            a,
+            c,
+            m,
+            shift,
+            state: seed % m,
+        }
+    }
+
+    /// glibc's `TYPE_0` LCG, which `random` only uses when `initstate` is
+    /// given an 8 byte state. The default `rand` is the additive `TYPE_3`
+    /// generator, not this one.
+    pub fn glibc(seed: u32) -> Lcg {
+        Lcg::new(1_103_515_245, 12345, 1 << 31, 0, seed as u64)
+    }
+
+    /// `java.util.Random`, which scrambles the seed and gives out the top
+    /// 32 of its 48 bits. `next_u64() as u32 as i32` matches `nextInt()`.
+    pub fn java(seed: u64) -> Lcg {
+        Lcg::new(0x5_DEEC_E66D, 0xB, 1 << 48, 16, seed ^ 0x5_DEEC_E66D)
+    }
+
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let next = self.a as u128 * self.state as u128 + self.c as u128;
+        self.state = (next % self.m as u128) as u64;
+        self.state >> self.shift
+    }
+}
+
+impl Iterator for Lcg {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        Some(self.next_u64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_java() {
+        // new Random(0).nextInt(), twice, and new Random(42).nextInt()
+        let mut rng = Lcg::java(0);
+        assert_eq!(rng.next_u64() as u32 as i32, -1155484576);
+        assert_eq!(rng.next_u64() as u32 as i32, -723955400);
+        assert_eq!(Lcg::java(42).next_u64() as u32 as i32, -1170105035);
+    }
+
+    #[test]
+    fn test_glibc() {
+        let mut rng = Lcg::glibc(1);
+        assert_eq!(rng.next_u64(), 1103527590);
+        assert!(rng.take(1000).all(|n| n < 1 << 31));
+    }
+}
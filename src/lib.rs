@@ -1,12 +1,22 @@
-mod cracking;
-mod decrypt;
-mod encode;
-mod encrypt;
-mod transform;
-mod padding;
+pub mod cracking;
+pub mod decrypt;
+pub mod encode;
+pub mod encrypt;
+pub mod lcg;
+pub mod mac;
+pub mod md4;
+pub mod merkle_damgard;
+pub mod mt19937;
+pub mod transform;
+pub mod padding;
+pub mod random;
+pub mod sha1;
+pub mod sha2;
+pub mod webapp;
 
 mod set_1;
 mod set_2;
+mod set_3;
+mod set_4;
 
-type Bytes = Vec<u8>;
-
+pub type Bytes = Vec<u8>;
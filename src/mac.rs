@@ -0,0 +1,59 @@
+//
+// Message authentication codes
+//
+// The naive way to key a hash is to stick the key on the front. Since the
+// digest is the hash's whole internal state, that lets anyone extend a
+// message and its MAC without knowing the key.
+//
+
+use crate::sha1::sha1;
+use crate::Bytes;
+
+pub fn secret_prefix_mac(key: &[u8], message: &[u8]) -> Bytes {
+    secret_prefix_mac_with(sha1, key, message)
+}
+
+pub fn verify_secret_prefix_mac(
+    key: &[u8],
+    message: &[u8],
+    mac: &[u8],
+) -> bool {
+    verify_secret_prefix_mac_with(sha1, key, message, mac)
+}
+
+// The same MAC over some other hash function.
+pub fn secret_prefix_mac_with(
+    hash: fn(&[u8]) -> Bytes,
+    key: &[u8],
+    message: &[u8],
+) -> Bytes {
+    hash(&[key, message].concat())
+}
+
+pub fn verify_secret_prefix_mac_with(
+    hash: fn(&[u8]) -> Bytes,
+    key: &[u8],
+    message: &[u8],
+    mac: &[u8],
+) -> bool {
+    secret_prefix_mac_with(hash, key, message) == mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::md4::md4;
+
+    #[test]
+    fn test_secret_prefix_mac() {
+        let mac = secret_prefix_mac(b"key", b"message");
+        assert!(verify_secret_prefix_mac(b"key", b"message", &mac));
+        assert!(!verify_secret_prefix_mac(b"key", b"messagf", &mac));
+        assert!(!verify_secret_prefix_mac(b"kez", b"message", &mac));
+
+        let mac = secret_prefix_mac_with(md4, b"key", b"message");
+        assert_eq!(mac.len(), 16);
+        assert!(verify_secret_prefix_mac_with(md4, b"key", b"message", &mac));
+        assert!(!verify_secret_prefix_mac(b"key", b"message", &mac));
+    }
+}
@@ -0,0 +1,165 @@
+//
+// MD4
+//
+// https://www.rfc-editor.org/rfc/rfc1320
+//
+// The ancestor of SHA-1 and built the same way, just with four registers,
+// three rounds, and everything little endian including the length at the
+// end of the padding.
+//
+
+use crate::merkle_damgard::{self, Compression, Hasher};
+use crate::Bytes;
+
+pub const BLOCK_SIZE: usize = 64;
+pub const DIGEST_SIZE: usize = 16;
+
+// Which message word each step of rounds two and three reads
+const ROUND_2_ORDER: [usize; 16] =
+    [0, 4, 8, 12, 1, 5, 9, 13, 2, 6, 10, 14, 3, 7, 11, 15];
+const ROUND_3_ORDER: [usize; 16] =
+    [0, 8, 4, 12, 2, 10, 6, 14, 1, 9, 5, 13, 3, 11, 7, 15];
+
+pub struct Md4Core;
+
+pub type Md4 = Hasher<Md4Core>;
+
+impl Compression for Md4Core {
+    type State = [u32; 4];
+
+    const BLOCK_SIZE: usize = BLOCK_SIZE;
+    const LENGTH_SIZE: usize = 8;
+    const BIG_ENDIAN: bool = false;
+    const INITIAL_STATE: [u32; 4] =
+        [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476];
+
+    fn compress(state: &mut [u32; 4], block: &[u8]) {
+        let mut x = [0u32; 16];
+        for (word, bytes) in x.iter_mut().zip(block.chunks(4)) {
+            *word =
+                u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        let mut r = *state;
+        for i in 0..48 {
+            // Steps update a, d, c, b in turn, mixing the other three
+            let t = [0, 3, 2, 1][i % 4];
+            let (b, c, d) = (r[(t + 1) % 4], r[(t + 2) % 4], r[(t + 3) % 4]);
+
+            let (mixed, k, constant, shifts) = match i / 16 {
+                0 => (f(b, c, d), i, 0, [3, 7, 11, 19]),
+                1 => (
+                    g(b, c, d),
+                    ROUND_2_ORDER[i % 16],
+                    0x5A82_7999,
+                    [3, 5, 9, 13],
+                ),
+                _ => (
+                    h(b, c, d),
+                    ROUND_3_ORDER[i % 16],
+                    0x6ED9_EBA1,
+                    [3, 9, 11, 15],
+                ),
+            };
+
+            r[t] = r[t]
+                .wrapping_add(mixed)
+                .wrapping_add(x[k])
+                .wrapping_add(constant)
+                .rotate_left(shifts[i % 4]);
+        }
+
+        for (register, value) in state.iter_mut().zip(r) {
+            *register = register.wrapping_add(value);
+        }
+    }
+
+    fn digest(state: &[u32; 4]) -> Bytes {
+        state.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    fn state(digest: &[u8]) -> Option<[u32; 4]> {
+        if digest.len() != DIGEST_SIZE {
+            return None;
+        }
+
+        let mut state = [0; 4];
+        for (word, bytes) in state.iter_mut().zip(digest.chunks(4)) {
+            *word =
+                u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        Some(state)
+    }
+}
+
+// The mixing function for each round
+fn f(x: u32, y: u32, z: u32) -> u32 {
+    (x & y) | (!x & z)
+}
+
+fn g(x: u32, y: u32, z: u32) -> u32 {
+    (x & y) | (x & z) | (y & z)
+}
+
+fn h(x: u32, y: u32, z: u32) -> u32 {
+    x ^ y ^ z
+}
+
+pub fn md4(data: &[u8]) -> Bytes {
+    merkle_damgard::hash::<Md4Core>(data)
+}
+
+// Like SHA-1's padding, but the length goes on little endian.
+pub fn glue_padding(message_len: u64) -> Bytes {
+    merkle_damgard::glue_padding::<Md4Core>(message_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode::bytes_to_hex;
+
+    #[test]
+    fn test_vectors() {
+        let cases: [(&[u8], &str); 7] = [
+            (b"", "31d6cfe0d16ae931b73c59d7e0c089c0"),
+            (b"a", "bde52cb31de33e46245e05fbdbd6fb24"),
+            (b"abc", "a448017aaf21d8525fc10ae87aa6729d"),
+            (b"message digest", "d9130a8164549fe818874806e1c7014b"),
+            (b"abcdefghijklmnopqrstuvwxyz", "d79e1c308aa5bbcdeea8ed63df412da9"),
+            (
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                "043f8582f241db351ce627e153e7f0e4",
+            ),
+            (
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                "e33b4ddc9c38f2199c3e7b164fcc0536",
+            ),
+        ];
+
+        for (message, digest) in cases.iter() {
+            assert_eq!(bytes_to_hex(md4(message)), *digest);
+        }
+    }
+
+    #[test]
+    fn test_glue_padding() {
+        let padding = glue_padding(3);
+        assert_eq!(padding.len(), 61);
+        assert_eq!(padding[0], 0x80);
+        assert_eq!(padding[53..], [24, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_from_digest() {
+        let message = b"The quick brown fox jumps over the lazy dog";
+        let glued =
+            [&message[..], &glue_padding(message.len() as u64)].concat();
+
+        let mut resumed =
+            Md4::from_digest(&md4(message), glued.len() as u64).unwrap();
+        resumed.update(b"more");
+
+        assert_eq!(resumed.finalize(), md4(&[&glued[..], b"more"].concat()));
+    }
+}
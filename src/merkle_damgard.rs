@@ -0,0 +1,123 @@
+//
+// The Merkle-Damgård construction
+//
+// https://en.wikipedia.org/wiki/Merkle%E2%80%93Damg%C3%A5rd_construction
+//
+// MD4, SHA-1 and SHA-2 all pad the message out to whole blocks, ending with
+// its length, and feed the blocks one at a time through a compression
+// function that updates a few registers. The digest is those registers
+// written out, so the streaming and padding code here is shared and each
+// hash only brings its compression function.
+//
+
+use crate::Bytes;
+use std::marker::PhantomData;
+
+pub trait Compression {
+    type State: Copy;
+
+    const BLOCK_SIZE: usize;
+    /// How many bytes the message length takes at the end of the padding.
+    const LENGTH_SIZE: usize;
+    const BIG_ENDIAN: bool;
+    const INITIAL_STATE: Self::State;
+
+    fn compress(state: &mut Self::State, block: &[u8]);
+
+    fn digest(state: &Self::State) -> Bytes;
+
+    /// The registers behind a digest, if the digest holds all of them.
+    fn state(digest: &[u8]) -> Option<Self::State>;
+}
+
+pub struct Hasher<C: Compression> {
+    state: C::State,
+    length: u64,
+    buffer: Bytes,
+    compression: PhantomData<C>,
+}
+
+impl<C: Compression> Hasher<C> {
+    pub fn new() -> Hasher<C> {
+        Hasher::from_state(C::INITIAL_STATE, 0)
+    }
+
+    /// A hasher with the given registers that has already processed
+    /// `length` bytes, which should be a whole number of blocks.
+    pub fn from_state(state: C::State, length: u64) -> Hasher<C> {
+        Hasher {
+            state,
+            length,
+            buffer: Vec::with_capacity(C::BLOCK_SIZE),
+            compression: PhantomData,
+        }
+    }
+
+    /// Pick up from a finished digest, as though the message and its padding
+    /// (`length` bytes in all) had just been hashed.
+    pub fn from_digest(digest: &[u8], length: u64) -> Option<Hasher<C>> {
+        Some(Hasher::from_state(C::state(digest)?, length))
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        self.buffer.extend_from_slice(data);
+
+        let full = self.buffer.len() - self.buffer.len() % C::BLOCK_SIZE;
+        for block in self.buffer[..full].chunks(C::BLOCK_SIZE) {
+            C::compress(&mut self.state, block);
+        }
+        self.buffer.drain(..full);
+    }
+
+    pub fn finalize(mut self) -> Bytes {
+        let padding = glue_padding::<C>(self.length);
+        self.update(&padding);
+
+        C::digest(&self.state)
+    }
+}
+
+impl<C: Compression> Clone for Hasher<C> {
+    fn clone(&self) -> Hasher<C> {
+        Hasher {
+            state: self.state,
+            length: self.length,
+            buffer: self.buffer.clone(),
+            compression: PhantomData,
+        }
+    }
+}
+
+impl<C: Compression> Default for Hasher<C> {
+    fn default() -> Hasher<C> {
+        Hasher::new()
+    }
+}
+
+pub fn hash<C: Compression>(data: &[u8]) -> Bytes {
+    let mut hasher = Hasher::<C>::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+//
+// The padding appended to a message of `message_len` bytes: a 1 bit, zeros
+// up to a block boundary less the length field, then the length in bits.
+//
+pub fn glue_padding<C: Compression>(message_len: u64) -> Bytes {
+    let tail = 1 + C::LENGTH_SIZE;
+    let zeros =
+        (C::BLOCK_SIZE * 2 - tail - message_len as usize % C::BLOCK_SIZE)
+            % C::BLOCK_SIZE;
+    let bits = (message_len as u128) * 8;
+
+    let mut padding = vec![0x80];
+    padding.resize(1 + zeros, 0);
+    if C::BIG_ENDIAN {
+        padding.extend_from_slice(&bits.to_be_bytes()[16 - C::LENGTH_SIZE..]);
+    } else {
+        padding.extend_from_slice(&bits.to_le_bytes()[..C::LENGTH_SIZE]);
+    }
+    padding
+}
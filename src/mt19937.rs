@@ -0,0 +1,217 @@
+//
+// MT19937 Mersenne Twister
+//
+// https://en.wikipedia.org/wiki/Mersenne_Twister
+//
+// The generator keeps 624 words of state. Every 624 outputs the whole state
+// is "twisted" into a new one, and each output is one state word put
+// through a "tempering" transform to spread its bits around.
+//
+
+pub const N: usize = 624;
+pub const M: usize = 397;
+pub const MATRIX_A: u32 = 0x9908_B0DF;
+const UPPER_MASK: u32 = 0x8000_0000;
+const LOWER_MASK: u32 = 0x7FFF_FFFF;
+
+pub struct Mt19937 {
+    state: [u32; N],
+    index: usize,
+}
+
+impl Mt19937 {
+    pub fn new(seed: u32) -> Mt19937 {
+        let mut state = [0; N];
+        state[0] = seed;
+        for i in 1..N {
+            let prev = state[i - 1];
+            state[i] = 1_812_433_253u32
+                .wrapping_mul(prev ^ (prev >> 30))
+                .wrapping_add(i as u32);
+        }
+
+        Mt19937 { state, index: N }
+    }
+
+    /// A generator that has just output the 624 words of `state`, untempered
+    /// and oldest first. The next call twists them into a fresh state.
+    pub fn from_state(state: [u32; N]) -> Mt19937 {
+        Mt19937 { state, index: N }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        if self.index >= N {
+            self.twist();
+        }
+
+        let y = self.state[self.index];
+        self.index += 1;
+        temper(y)
+    }
+
+    /// Bytes taken from the low 8 bits of each output.
+    pub fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_u32() as u8).collect()
+    }
+
+    fn twist(&mut self) {
+        for i in 0..N {
+            let y = (self.state[i] & UPPER_MASK)
+                | (self.state[(i + 1) % N] & LOWER_MASK);
+            let mut next = self.state[(i + M) % N] ^ (y >> 1);
+            if y & 1 == 1 {
+                next ^= MATRIX_A;
+            }
+            self.state[i] = next;
+        }
+
+        self.index = 0;
+    }
+}
+
+impl Iterator for Mt19937 {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        Some(self.next_u32())
+    }
+}
+
+pub fn temper(mut y: u32) -> u32 {
+    y ^= y >> 11;
+    y ^= (y << 7) & 0x9D2C_5680;
+    y ^= (y << 15) & 0xEFC6_0000;
+    y ^= y >> 18;
+    y
+}
+
+//
+// Undo `temper`, one step at a time in reverse.
+//
+// Each step XORs the value with a shifted copy of itself. The bits shifted
+// in from outside are zero, so the first `shift` bits come through as they
+// were, and each pass recovers the next `shift` bits from the ones before.
+//
+pub fn untemper(mut y: u32) -> u32 {
+    y = unshift_right(y, 18);
+    y = unshift_left(y, 15, 0xEFC6_0000);
+    y = unshift_left(y, 7, 0x9D2C_5680);
+    y = unshift_right(y, 11);
+    y
+}
+
+fn unshift_right(y: u32, shift: u32) -> u32 {
+    let mut x = y;
+    for _ in 0..32 / shift {
+        x = y ^ (x >> shift);
+    }
+    x
+}
+
+fn unshift_left(y: u32, shift: u32, mask: u32) -> u32 {
+    let mut x = y;
+    for _ in 0..32 / shift {
+        x = y ^ ((x << shift) & mask);
+    }
+    x
+}
+
+const N_64: usize = 312;
+const M_64: usize = 156;
+const MATRIX_A_64: u64 = 0xB502_6F5A_A966_19E9;
+const UPPER_MASK_64: u64 = 0xFFFF_FFFF_8000_0000;
+const LOWER_MASK_64: u64 = 0x7FFF_FFFF;
+
+/// The 64 bit variant, MT19937-64. Same idea, with 312 words of state.
+pub struct Mt19937_64 {
+    state: [u64; N_64],
+    index: usize,
+}
+
+impl Mt19937_64 {
+    pub fn new(seed: u64) -> Mt19937_64 {
+        let mut state = [0; N_64];
+        state[0] = seed;
+        for i in 1..N_64 {
+            let prev = state[i - 1];
+            state[i] = 6_364_136_223_846_793_005u64
+                .wrapping_mul(prev ^ (prev >> 62))
+                .wrapping_add(i as u64);
+        }
+
+        Mt19937_64 { state, index: N_64 }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        if self.index >= N_64 {
+            self.twist();
+        }
+
+        let mut y = self.state[self.index];
+        self.index += 1;
+
+        y ^= (y >> 29) & 0x5555_5555_5555_5555;
+        y ^= (y << 17) & 0x71D6_7FFF_EDA6_0000;
+        y ^= (y << 37) & 0xFFF7_EEE0_0000_0000;
+        y ^= y >> 43;
+        y
+    }
+
+    fn twist(&mut self) {
+        for i in 0..N_64 {
+            let y = (self.state[i] & UPPER_MASK_64)
+                | (self.state[(i + 1) % N_64] & LOWER_MASK_64);
+            let mut next = self.state[(i + M_64) % N_64] ^ (y >> 1);
+            if y & 1 == 1 {
+                next ^= MATRIX_A_64;
+            }
+            self.state[i] = next;
+        }
+
+        self.index = 0;
+    }
+}
+
+impl Iterator for Mt19937_64 {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        Some(self.next_u64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mt19937() {
+        // Outputs of the reference implementation with its default seed
+        let mut rng = Mt19937::new(5489);
+        assert_eq!(
+            rng.by_ref().take(5).collect::<Vec<u32>>(),
+            vec![3499211612, 581869302, 3890346734, 3586334585, 545404204]
+        );
+
+        // C++ requires the 10000th output of a default std::mt19937
+        assert_eq!(rng.nth(10000 - 6), Some(4123659995));
+    }
+
+    #[test]
+    fn test_untemper() {
+        for y in
+            [0, 1, 0x8000_0000, 0xFFFF_FFFF, 0xDEAD_BEEF, 1131464071].iter()
+        {
+            assert_eq!(untemper(temper(*y)), *y);
+        }
+    }
+
+    #[test]
+    fn test_mt19937_64() {
+        let mut rng = Mt19937_64::new(5489);
+        assert_eq!(rng.next_u64(), 14514284786278117030);
+
+        // And of a default std::mt19937_64
+        assert_eq!(rng.nth(10000 - 2), Some(9981545732273789042));
+    }
+}
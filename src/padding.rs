@@ -2,11 +2,23 @@ use crate::Bytes;
 
 pub fn pkcs7(bytes: &Bytes, len: u8) -> Bytes {
   let mut out = bytes.clone();
-  let to_add: u8 = len - (bytes.len() as u8 % len);
-  println!("{:?}", to_add);
-  println!("{:?}", out);
+  let to_add: u8 = len - (bytes.len() % len as usize) as u8;
   let mut add_vec = vec![to_add; to_add as usize];
   out.append(&mut add_vec);
-  println!("{:?}", out);
   out
 }
+
+// Remove PKCS#7 padding, or None if the padding isn't valid.
+pub fn strip_pkcs7(bytes: &Bytes, len: u8) -> Option<Bytes> {
+  let last = *bytes.last()?;
+  if last == 0 || last > len || !bytes.len().is_multiple_of(len as usize) {
+    return None;
+  }
+
+  let (data, pad) = bytes.split_at(bytes.len() - last as usize);
+  if pad.iter().all(|b| *b == last) {
+    Some(data.to_vec())
+  } else {
+    None
+  }
+}
@@ -0,0 +1,120 @@
+//
+// Sources of randomness.
+//
+// Keys, IVs and prefixes come from the OS by default. Anything that takes
+// an `Rng` can be handed a `SeededRng` instead to make tests repeatable.
+//
+
+use crate::mt19937::Mt19937_64;
+use crate::Bytes;
+use std::fs::File;
+use std::io::Read;
+
+pub trait Rng {
+    fn fill(&mut self, buf: &mut [u8]);
+
+    fn bytes(&mut self, len: usize) -> Bytes {
+        let mut out = vec![0; len];
+        self.fill(&mut out);
+        out
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0; 8];
+        self.fill(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    // A random number in `low..high`. Values from the top of the u64 range
+    // that would favour the smaller results get thrown back.
+    fn range(&mut self, low: usize, high: usize) -> usize {
+        assert!(low < high);
+
+        let span = (high - low) as u64;
+        let limit = u64::MAX - u64::MAX % span;
+        loop {
+            let value = self.next_u64();
+            if value < limit {
+                return low + (value % span) as usize;
+            }
+        }
+    }
+}
+
+/// Reads from `/dev/urandom`.
+pub struct OsRng;
+
+impl Rng for OsRng {
+    fn fill(&mut self, buf: &mut [u8]) {
+        File::open("/dev/urandom")
+            .and_then(|mut file| file.read_exact(buf))
+            .expect("couldn't read /dev/urandom");
+    }
+}
+
+/// The same stream of bytes every time for a given seed.
+pub struct SeededRng(Mt19937_64);
+
+impl SeededRng {
+    pub fn new(seed: u64) -> SeededRng {
+        SeededRng(Mt19937_64::new(seed))
+    }
+}
+
+impl Rng for SeededRng {
+    fn fill(&mut self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(8) {
+            let word = self.0.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+}
+
+pub fn bytes(len: usize) -> Bytes {
+    OsRng.bytes(len)
+}
+
+// A random number in `low..high`.
+pub fn range(low: usize, high: usize) -> usize {
+    OsRng.range(low, high)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_rng() {
+        let mut a = SeededRng::new(42);
+        let mut b = SeededRng::new(42);
+
+        assert_eq!(a.bytes(37), b.bytes(37));
+        assert_eq!(a.range(0, 1000), b.range(0, 1000));
+        assert_ne!(a.bytes(16), SeededRng::new(43).bytes(16));
+    }
+
+    #[test]
+    fn test_range() {
+        let mut rng = SeededRng::new(7);
+        let mut seen = [false; 10];
+
+        for _ in 0..1000 {
+            let value = rng.range(5, 15);
+            assert!((5..15).contains(&value));
+            seen[value - 5] = true;
+        }
+        assert!(seen.iter().all(|s| *s));
+
+        assert_eq!(OsRng.range(3, 4), 3);
+    }
+
+    #[test]
+    fn test_os_rng() {
+        assert_eq!(bytes(0), Bytes::new());
+        assert_ne!(bytes(32), bytes(32));
+    }
+}
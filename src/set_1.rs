@@ -50,13 +50,15 @@ fn test_challenge_4() {
   // 2. read hex into bytes
   // 3. for each line, find best single char cipher
   // 4. select best score overall
-  let (_, _, bytes) = fs::read_to_string("4/data.txt")
+  let lines: Vec<Bytes> = fs::read_to_string("4/data.txt")
     .unwrap()
     .split("\n")
-    .map(|line| encode::hex_to_bytes(line))
-    .map(|bytes| cracking::single_byte_cipher(&bytes))
-    .max_by_key(|(s, _, _)| *s)
-    .unwrap();
+    .map(encode::hex_to_bytes)
+    .collect();
+  let ranked = cracking::detect_single_byte_cipher(&lines);
+  let bytes = &ranked[0].plaintext;
+
+  assert_eq!((ranked[0].line, ranked[0].key), (170, b'5'));
 
     assert_eq!(
       bytes.iter().map(|by| *by as char).collect::<String>(),
@@ -112,7 +114,7 @@ fn test_challenge_8() {
   let content = fs::read_to_string("8/data.txt").unwrap();
   let entries = content
     .split("\n")
-    .map(|s| encode::hex_to_bytes(s))
+    .map(encode::hex_to_bytes)
     .collect();
     let ecb_encrypted = cracking::find_ecb(&entries);
     assert_eq!(
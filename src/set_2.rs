@@ -15,3 +15,114 @@ fn test_challenge_9() {
     "YELLOW SUBMARINE\x04\x04\x04\x04".as_bytes()
   )
 }
+
+#[cfg(test)]
+fn unknown_string() -> Bytes {
+  encode::b64_to_bytes(
+    "Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkg\
+     aGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBq\
+     dXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUg\
+     YnkK",
+  )
+}
+
+#[test]
+fn test_challenge_12() {
+  use cracking::oracle::{from_fn, Oracle};
+
+  let key = random::bytes(16);
+  let secret = unknown_string();
+  let mut oracle = from_fn(|input: &Bytes| {
+    let mut plaintext = input.clone();
+    plaintext.extend(&secret);
+    encrypt::aes_ecb(&plaintext, &key)
+  })
+  .counted();
+
+  let profile = cracking::ecb::profile(&mut oracle).unwrap();
+  assert_eq!(profile.block_size, 16);
+  assert!(profile.ecb);
+  assert_eq!(profile.suffix_len, Some(secret.len()));
+
+  let recovered = cracking::ecb::byte_at_a_time(&mut oracle, false);
+  assert!(oracle.queries() < 1000);
+  assert_eq!(recovered, Some(secret));
+}
+
+#[test]
+fn test_challenge_13() {
+  let mut service = webapp::ProfileService::new();
+
+  let forged = cracking::ecb::cut_and_paste(
+    &mut service,
+    "user".len(),
+    &"admin".as_bytes().to_vec(),
+  )
+  .unwrap();
+
+  let profile = service.decrypt_profile(&forged);
+  assert_eq!(profile[1], ("uid".to_string(), "10".to_string()));
+  assert_eq!(profile[2], ("role".to_string(), "admin".to_string()));
+  assert_eq!(service.role(&forged), Some("admin".to_string()));
+}
+
+#[test]
+fn test_challenge_14() {
+  use cracking::oracle::from_fn;
+
+  let key = random::bytes(16);
+  let secret = unknown_string();
+  let prefix = random::bytes(random::range(1, 64));
+
+  // A random prefix that's the same every time
+  let mut oracle = from_fn(|input: &Bytes| {
+    let mut plaintext = prefix.clone();
+    plaintext.extend(input);
+    plaintext.extend(&secret);
+    encrypt::aes_ecb(&plaintext, &key)
+  });
+
+  assert_eq!(
+    cracking::ecb::byte_at_a_time(&mut oracle, false),
+    Some(secret.clone())
+  );
+  assert_eq!(
+    cracking::ecb::byte_at_a_time(&mut oracle, true),
+    Some(secret.clone())
+  );
+
+  // A new random prefix on every query
+  let mut oracle = from_fn(|input: &Bytes| {
+    let mut plaintext = random::bytes(random::range(0, 48));
+    plaintext.extend(input);
+    plaintext.extend(&secret);
+    encrypt::aes_ecb(&plaintext, &key)
+  });
+
+  assert_eq!(cracking::ecb::byte_at_a_time(&mut oracle, true), Some(secret));
+}
+
+#[test]
+fn test_challenge_15() {
+  let strip = |input: &str| padding::strip_pkcs7(&input.as_bytes().to_vec(), 16);
+
+  assert_eq!(
+    strip("ICE ICE BABY\x04\x04\x04\x04"),
+    Some("ICE ICE BABY".as_bytes().to_vec())
+  );
+  assert_eq!(strip("ICE ICE BABY\x05\x05\x05\x05"), None);
+  assert_eq!(strip("ICE ICE BABY\x01\x02\x03\x04"), None);
+}
+
+#[test]
+fn test_challenge_16() {
+  let mut service = webapp::CommentService::new();
+  let target = ";admin=true;".as_bytes().to_vec();
+
+  let (forged, scrambled) =
+    cracking::cbc::bit_flip(&mut service, &target).unwrap();
+
+  // "comment1=cooking%20MCs;userdata=" is exactly two blocks
+  assert_eq!(scrambled, 2);
+  assert!(service.is_admin(&forged));
+}
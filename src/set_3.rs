@@ -0,0 +1,182 @@
+//
+// Set 3: Block & Stream Crypto
+//
+// https://cryptopals.com/sets/3
+//
+
+#[cfg(test)]
+use crate::*;
+
+#[test]
+fn test_challenge_17() {
+  let mut service = webapp::PaddingService::new();
+  let strings = webapp::PaddingService::strings();
+
+  for _ in 0..10 {
+    let (iv, ciphertext) = service.encrypt_random();
+    let (plaintext, queries) =
+      cracking::cbc::padding_oracle(&mut service, &iv, &ciphertext).unwrap();
+
+    assert!(strings.contains(&plaintext));
+    assert!(queries > 0);
+  }
+}
+
+#[test]
+fn test_challenge_18() {
+  let ciphertext = encode::b64_to_bytes(
+    "L77na/nrFsKvynd6HzOoG7GHTLXsTVu9qvY/2syLXzhPweyyMTJULu/6/kXX0KSvoOLSFQ==",
+  );
+  let key = "YELLOW SUBMARINE".as_bytes().to_vec();
+  let plaintext = decrypt::aes_ctr(&ciphertext, &key, 0);
+
+  assert_eq!(
+    plaintext,
+    "Yo, VIP Let's kick it Ice, Ice, baby Ice, Ice, baby ".as_bytes()
+  );
+  assert_eq!(encrypt::aes_ctr(&plaintext, &key, 0), ciphertext);
+}
+
+#[cfg(test)]
+const EASTER_1916: [&str; 40] = [
+  "I have met them at close of day",
+  "Coming with vivid faces",
+  "From counter or desk among grey",
+  "Eighteenth-century houses.",
+  "I have passed with a nod of the head",
+  "Or polite meaningless words,",
+  "Or have lingered awhile and said",
+  "Polite meaningless words,",
+  "And thought before I had done",
+  "Of a mocking tale or a gibe",
+  "To please a companion",
+  "Around the fire at the club,",
+  "Being certain that they and I",
+  "But lived where motley is worn:",
+  "All changed, changed utterly:",
+  "A terrible beauty is born.",
+  "That woman's days were spent",
+  "In ignorant good will,",
+  "Her nights in argument",
+  "Until her voice grew shrill.",
+  "What voice more sweet than hers",
+  "When young and beautiful,",
+  "She rode to harriers?",
+  "This man had kept a school",
+  "And rode our winged horse.",
+  "This other his helper and friend",
+  "Was coming into his force;",
+  "He might have won fame in the end,",
+  "So sensitive his nature seemed,",
+  "So daring and sweet his thought.",
+  "This other man I had dreamed",
+  "A drunken, vain-glorious lout.",
+  "He had done most bitter wrong",
+  "To some who are near my heart,",
+  "Yet I number him in the song;",
+  "He, too, has resigned his part",
+  "In the casual comedy;",
+  "He, too, has been changed in his turn,",
+  "Transformed utterly:",
+  "A terrible beauty is born.",
+];
+
+// Fraction of the keystream bytes that came out right.
+#[cfg(test)]
+fn keystream_accuracy(found: &Bytes, actual: &Bytes) -> f64 {
+  let right = found.iter().zip(actual).filter(|(a, b)| a == b).count();
+  right as f64 / found.len() as f64
+}
+
+#[test]
+fn test_challenge_19() {
+  use random::Rng;
+
+  // A fixed key keeps the accuracy thresholds below deterministic
+  let key = random::SeededRng::new(19).bytes(16);
+  let ciphertexts: Vec<Bytes> = EASTER_1916
+    .iter()
+    .map(|line| encrypt::aes_ctr(&line.as_bytes().to_vec(), &key, 0))
+    .collect();
+  let actual = encrypt::aes_ctr(&vec![0; 64], &key, 0);
+
+  // Capitals at the start of each line throw the first byte off, and the
+  // tail has only a couple of lines left to go on
+  let keystream = cracking::ctr::fixed_nonce_keystream(&ciphertexts);
+  assert_eq!(keystream.len(), 38);
+  assert!(keystream_accuracy(&keystream[..30].to_vec(), &actual) >= 0.85);
+  assert!(keystream_accuracy(&keystream, &actual) >= 0.75);
+}
+
+#[test]
+fn test_challenge_20() {
+  use random::Rng;
+
+  let key = random::SeededRng::new(20).bytes(16);
+  let ciphertexts: Vec<Bytes> = EASTER_1916
+    .iter()
+    .map(|line| encrypt::aes_ctr(&line.as_bytes().to_vec(), &key, 0))
+    .collect();
+  let actual = encrypt::aes_ctr(&vec![0; 64], &key, 0);
+
+  let keystream = cracking::ctr::fixed_nonce_truncated(&ciphertexts);
+  assert_eq!(keystream.len(), 20);
+  assert!(keystream_accuracy(&keystream, &actual) >= 0.9);
+}
+
+#[test]
+fn test_challenge_21() {
+  let mut rng = mt19937::Mt19937::new(1131464071);
+  let mut again = mt19937::Mt19937::new(1131464071);
+
+  let outputs: Vec<u32> = rng.by_ref().take(1000).collect();
+  assert_eq!(outputs, again.by_ref().take(1000).collect::<Vec<u32>>());
+  assert_ne!(
+    outputs,
+    mt19937::Mt19937::new(1131464072).take(1000).collect::<Vec<u32>>()
+  );
+}
+
+#[test]
+fn test_challenge_22() {
+  use std::time::{SystemTime, UNIX_EPOCH};
+
+  // Rather than actually waiting around, pretend the clock moved on
+  let now = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as u32;
+  let seeded_at = now - random::range(40, 1000) as u32;
+  let output = mt19937::Mt19937::new(seeded_at).next_u32();
+
+  assert_eq!(
+    cracking::mt19937::timestamp_seed(output, now, 2000),
+    Some(seeded_at)
+  );
+}
+
+#[test]
+fn test_challenge_23() {
+  let mut rng = mt19937::Mt19937::new(random::range(0, 1 << 32) as u32);
+  let outputs: Vec<u32> = rng.by_ref().take(624).collect();
+
+  let mut cloned = cracking::mt19937::clone(&outputs).unwrap();
+  for _ in 0..1000 {
+    assert_eq!(cloned.next_u32(), rng.next_u32());
+  }
+}
+
+#[test]
+fn test_challenge_24() {
+  let seed = random::range(0, 0x10000) as u16;
+  let mut plaintext = random::bytes(random::range(0, 32));
+  plaintext.extend(vec![b'A'; 14]);
+
+  let ciphertext = encrypt::mt19937_cipher(&plaintext, seed);
+  assert_eq!(decrypt::mt19937_cipher(&ciphertext, seed), plaintext);
+
+  assert_eq!(
+    cracking::mt19937::cipher_seed(&ciphertext, &vec![b'A'; 14]),
+    Some(seed)
+  );
+}
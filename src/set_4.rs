@@ -0,0 +1,115 @@
+//
+// Set 4: Stream Crypto and Randomness
+//
+// https://cryptopals.com/sets/4
+//
+
+#[cfg(test)]
+use crate::*;
+
+#[test]
+fn test_challenge_25() {
+  use std::fs;
+
+  let content = fs::read_to_string("7/data.txt").unwrap();
+  let plaintext = decrypt::aes_ecb(
+    &encode::b64_to_bytes(&content),
+    &"YELLOW SUBMARINE".as_bytes().to_vec(),
+  );
+
+  let mut service = webapp::DiskService::new();
+  let ciphertext = service.encrypt(&plaintext);
+
+  assert_eq!(
+    cracking::ctr::edit_recover(&mut service, &ciphertext),
+    plaintext
+  );
+}
+
+#[test]
+fn test_challenge_26() {
+  let mut service = webapp::CtrCommentService::new();
+  let target = ";admin=true;".as_bytes().to_vec();
+
+  let forged = cracking::ctr::bit_flip(&mut service, &target).unwrap();
+  assert!(service.is_admin(&forged));
+
+  // Only the bytes under the target changed
+  let original = service.encrypt_userdata(&"A".repeat(target.len()));
+  let changed: Vec<usize> = (0..forged.len())
+    .filter(|i| forged[*i] != original[*i])
+    .collect();
+  assert!(changed.iter().all(|i| (32..32 + target.len()).contains(i)));
+}
+
+#[test]
+fn test_challenge_27() {
+  let mut service = webapp::KeyIvService::new();
+  let ciphertext = service.encrypt_userdata("just a normal user");
+
+  let key = cracking::cbc::key_as_iv(&mut service, &ciphertext, 16).unwrap();
+
+  assert_eq!(
+    decrypt::aes_cbc(&ciphertext, &key, &key),
+    webapp::comment_for("just a normal user").as_bytes()
+  );
+}
+
+#[test]
+fn test_challenge_28() {
+  let key = "YELLOW SUBMARINE".as_bytes();
+  let message = "We all live in a yellow submarine".as_bytes();
+  let tag = mac::secret_prefix_mac(key, message);
+
+  assert!(mac::verify_secret_prefix_mac(key, message, &tag));
+  assert!(!mac::verify_secret_prefix_mac(
+    key,
+    "We all live in a yellow submarinf".as_bytes(),
+    &tag
+  ));
+  assert!(!mac::verify_secret_prefix_mac(
+    "PURPLE SUBMARINE".as_bytes(),
+    message,
+    &tag
+  ));
+  assert_ne!(tag, sha1::sha1(message));
+}
+
+#[test]
+fn test_challenge_29() {
+  let mut service = webapp::MacService::new();
+  let (message, tag) = service.signed_comment();
+  assert!(!service.is_admin(&message, &tag));
+
+  let (forged, forged_tag) = cracking::mac::sha1_length_extension(
+    &mut service,
+    &message,
+    &tag,
+    b";admin=true",
+    0..=64,
+  )
+  .unwrap();
+
+  assert!(forged.starts_with(&message));
+  assert!(service.is_admin(&forged, &forged_tag));
+}
+
+#[test]
+fn test_challenge_30() {
+  let mut service =
+    webapp::MacService::with_hash(&mut random::OsRng, md4::md4);
+  let (message, tag) = service.signed_comment();
+  assert_eq!(tag.len(), 16);
+
+  let (forged, forged_tag) = cracking::mac::md4_length_extension(
+    &mut service,
+    &message,
+    &tag,
+    b";admin=true",
+    0..=64,
+  )
+  .unwrap();
+
+  assert!(forged.starts_with(&message));
+  assert!(service.is_admin(&forged, &forged_tag));
+}
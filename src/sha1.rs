@@ -0,0 +1,154 @@
+//
+// SHA-1
+//
+// https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf
+//
+// A Merkle-Damgård hash: the message is padded out to whole 64 byte blocks,
+// and each block is mixed into five 32 bit registers. The digest is just the
+// registers, so anyone holding a digest can carry on hashing from it.
+//
+
+use crate::merkle_damgard::{self, Compression, Hasher};
+use crate::Bytes;
+
+pub const BLOCK_SIZE: usize = 64;
+pub const DIGEST_SIZE: usize = 20;
+
+pub struct Sha1Core;
+
+pub type Sha1 = Hasher<Sha1Core>;
+
+impl Compression for Sha1Core {
+    type State = [u32; 5];
+
+    const BLOCK_SIZE: usize = BLOCK_SIZE;
+    const LENGTH_SIZE: usize = 8;
+    const BIG_ENDIAN: bool = true;
+    const INITIAL_STATE: [u32; 5] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+
+    fn compress(state: &mut [u32; 5], block: &[u8]) {
+        let mut w = [0u32; 80];
+        for (i, bytes) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = *state;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (register, value) in state.iter_mut().zip([a, b, c, d, e]) {
+            *register = register.wrapping_add(value);
+        }
+    }
+
+    fn digest(state: &[u32; 5]) -> Bytes {
+        state.iter().flat_map(|word| word.to_be_bytes()).collect()
+    }
+
+    fn state(digest: &[u8]) -> Option<[u32; 5]> {
+        if digest.len() != DIGEST_SIZE {
+            return None;
+        }
+
+        let mut state = [0; 5];
+        for (word, bytes) in state.iter_mut().zip(digest.chunks(4)) {
+            *word =
+                u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        Some(state)
+    }
+}
+
+pub fn sha1(data: &[u8]) -> Bytes {
+    merkle_damgard::hash::<Sha1Core>(data)
+}
+
+//
+// The padding SHA-1 appends to a message of `message_len` bytes: a 1 bit,
+// zeros up to 8 bytes short of a block boundary, then the length in bits as
+// a big endian u64.
+//
+pub fn glue_padding(message_len: u64) -> Bytes {
+    merkle_damgard::glue_padding::<Sha1Core>(message_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode::bytes_to_hex;
+
+    #[test]
+    fn test_vectors() {
+        let cases: [(&[u8], &str); 3] = [
+            (b"", "da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+            (b"abc", "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+            ),
+        ];
+
+        for (message, digest) in cases.iter() {
+            assert_eq!(bytes_to_hex(sha1(message)), *digest);
+        }
+    }
+
+    #[test]
+    fn test_streaming() {
+        // A million 'a's, fed in uneven pieces
+        let mut hasher = Sha1::new();
+        let chunk = [b'a'; 999];
+        let mut fed = 0;
+        while fed < 1_000_000 {
+            let len = chunk.len().min(1_000_000 - fed);
+            hasher.update(&chunk[..len]);
+            fed += len;
+        }
+
+        assert_eq!(
+            bytes_to_hex(hasher.finalize()),
+            "34aa973cd4c4daa4f61eeb2bdbad27316534016f"
+        );
+    }
+
+    #[test]
+    fn test_from_digest() {
+        let message = b"The quick brown fox jumps over the lazy dog";
+        let padding = glue_padding(message.len() as u64);
+        assert_eq!((message.len() + padding.len()) % BLOCK_SIZE, 0);
+
+        let glued = [&message[..], &padding].concat();
+        let mut resumed =
+            Sha1::from_digest(&sha1(message), glued.len() as u64).unwrap();
+        resumed.update(b"more");
+
+        assert_eq!(resumed.finalize(), sha1(&[&glued[..], b"more"].concat()));
+    }
+}
@@ -0,0 +1,515 @@
+//
+// SHA-2: SHA-224, SHA-256, SHA-384 and SHA-512
+//
+// https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf
+//
+// Merkle-Damgård hashes like SHA-1, with eight registers. SHA-256 works on
+// 32 bit words and 64 byte blocks, SHA-512 on 64 bit words and 128 byte
+// blocks with a 16 byte length field. SHA-224 and SHA-384 are those two
+// with different starting registers and a digest cut short, which leaves
+// out some of the state and so can't be extended from the digest alone.
+//
+
+use crate::merkle_damgard::{self, Compression, Hasher};
+use crate::Bytes;
+use std::convert::TryInto;
+
+// The fractional parts of the cube roots of the first 64 primes
+const K256: [u32; 64] = [
+    0x428A_2F98,
+    0x7137_4491,
+    0xB5C0_FBCF,
+    0xE9B5_DBA5,
+    0x3956_C25B,
+    0x59F1_11F1,
+    0x923F_82A4,
+    0xAB1C_5ED5,
+    0xD807_AA98,
+    0x1283_5B01,
+    0x2431_85BE,
+    0x550C_7DC3,
+    0x72BE_5D74,
+    0x80DE_B1FE,
+    0x9BDC_06A7,
+    0xC19B_F174,
+    0xE49B_69C1,
+    0xEFBE_4786,
+    0x0FC1_9DC6,
+    0x240C_A1CC,
+    0x2DE9_2C6F,
+    0x4A74_84AA,
+    0x5CB0_A9DC,
+    0x76F9_88DA,
+    0x983E_5152,
+    0xA831_C66D,
+    0xB003_27C8,
+    0xBF59_7FC7,
+    0xC6E0_0BF3,
+    0xD5A7_9147,
+    0x06CA_6351,
+    0x1429_2967,
+    0x27B7_0A85,
+    0x2E1B_2138,
+    0x4D2C_6DFC,
+    0x5338_0D13,
+    0x650A_7354,
+    0x766A_0ABB,
+    0x81C2_C92E,
+    0x9272_2C85,
+    0xA2BF_E8A1,
+    0xA81A_664B,
+    0xC24B_8B70,
+    0xC76C_51A3,
+    0xD192_E819,
+    0xD699_0624,
+    0xF40E_3585,
+    0x106A_A070,
+    0x19A4_C116,
+    0x1E37_6C08,
+    0x2748_774C,
+    0x34B0_BCB5,
+    0x391C_0CB3,
+    0x4ED8_AA4A,
+    0x5B9C_CA4F,
+    0x682E_6FF3,
+    0x748F_82EE,
+    0x78A5_636F,
+    0x84C8_7814,
+    0x8CC7_0208,
+    0x90BE_FFFA,
+    0xA450_6CEB,
+    0xBEF9_A3F7,
+    0xC671_78F2,
+];
+
+// The same for the first 80 primes, to 64 bits
+const K512: [u64; 80] = [
+    0x428A_2F98_D728_AE22,
+    0x7137_4491_23EF_65CD,
+    0xB5C0_FBCF_EC4D_3B2F,
+    0xE9B5_DBA5_8189_DBBC,
+    0x3956_C25B_F348_B538,
+    0x59F1_11F1_B605_D019,
+    0x923F_82A4_AF19_4F9B,
+    0xAB1C_5ED5_DA6D_8118,
+    0xD807_AA98_A303_0242,
+    0x1283_5B01_4570_6FBE,
+    0x2431_85BE_4EE4_B28C,
+    0x550C_7DC3_D5FF_B4E2,
+    0x72BE_5D74_F27B_896F,
+    0x80DE_B1FE_3B16_96B1,
+    0x9BDC_06A7_25C7_1235,
+    0xC19B_F174_CF69_2694,
+    0xE49B_69C1_9EF1_4AD2,
+    0xEFBE_4786_384F_25E3,
+    0x0FC1_9DC6_8B8C_D5B5,
+    0x240C_A1CC_77AC_9C65,
+    0x2DE9_2C6F_592B_0275,
+    0x4A74_84AA_6EA6_E483,
+    0x5CB0_A9DC_BD41_FBD4,
+    0x76F9_88DA_8311_53B5,
+    0x983E_5152_EE66_DFAB,
+    0xA831_C66D_2DB4_3210,
+    0xB003_27C8_98FB_213F,
+    0xBF59_7FC7_BEEF_0EE4,
+    0xC6E0_0BF3_3DA8_8FC2,
+    0xD5A7_9147_930A_A725,
+    0x06CA_6351_E003_826F,
+    0x1429_2967_0A0E_6E70,
+    0x27B7_0A85_46D2_2FFC,
+    0x2E1B_2138_5C26_C926,
+    0x4D2C_6DFC_5AC4_2AED,
+    0x5338_0D13_9D95_B3DF,
+    0x650A_7354_8BAF_63DE,
+    0x766A_0ABB_3C77_B2A8,
+    0x81C2_C92E_47ED_AEE6,
+    0x9272_2C85_1482_353B,
+    0xA2BF_E8A1_4CF1_0364,
+    0xA81A_664B_BC42_3001,
+    0xC24B_8B70_D0F8_9791,
+    0xC76C_51A3_0654_BE30,
+    0xD192_E819_D6EF_5218,
+    0xD699_0624_5565_A910,
+    0xF40E_3585_5771_202A,
+    0x106A_A070_32BB_D1B8,
+    0x19A4_C116_B8D2_D0C8,
+    0x1E37_6C08_5141_AB53,
+    0x2748_774C_DF8E_EB99,
+    0x34B0_BCB5_E19B_48A8,
+    0x391C_0CB3_C5C9_5A63,
+    0x4ED8_AA4A_E341_8ACB,
+    0x5B9C_CA4F_7763_E373,
+    0x682E_6FF3_D6B2_B8A3,
+    0x748F_82EE_5DEF_B2FC,
+    0x78A5_636F_4317_2F60,
+    0x84C8_7814_A1F0_AB72,
+    0x8CC7_0208_1A64_39EC,
+    0x90BE_FFFA_2363_1E28,
+    0xA450_6CEB_DE82_BDE9,
+    0xBEF9_A3F7_B2C6_7915,
+    0xC671_78F2_E372_532B,
+    0xCA27_3ECE_EA26_619C,
+    0xD186_B8C7_21C0_C207,
+    0xEADA_7DD6_CDE0_EB1E,
+    0xF57D_4F7F_EE6E_D178,
+    0x06F0_67AA_7217_6FBA,
+    0x0A63_7DC5_A2C8_98A6,
+    0x113F_9804_BEF9_0DAE,
+    0x1B71_0B35_131C_471B,
+    0x28DB_77F5_2304_7D84,
+    0x32CA_AB7B_40C7_2493,
+    0x3C9E_BE0A_15C9_BEBC,
+    0x431D_67C4_9C10_0D4C,
+    0x4CC5_D4BE_CB3E_42B6,
+    0x597F_299C_FC65_7E2A,
+    0x5FCB_6FAB_3AD6_FAEC,
+    0x6C44_198C_4A47_5817,
+];
+
+pub struct Sha224Core;
+pub struct Sha256Core;
+pub struct Sha384Core;
+pub struct Sha512Core;
+
+pub type Sha224 = Hasher<Sha224Core>;
+pub type Sha256 = Hasher<Sha256Core>;
+pub type Sha384 = Hasher<Sha384Core>;
+pub type Sha512 = Hasher<Sha512Core>;
+
+impl Compression for Sha224Core {
+    type State = [u32; 8];
+
+    const BLOCK_SIZE: usize = 64;
+    const LENGTH_SIZE: usize = 8;
+    const BIG_ENDIAN: bool = true;
+    const INITIAL_STATE: [u32; 8] = [
+        0xC105_9ED8,
+        0x367C_D507,
+        0x3070_DD17,
+        0xF70E_5939,
+        0xFFC0_0B31,
+        0x6858_1511,
+        0x64F9_8FA7,
+        0xBEFA_4FA4,
+    ];
+
+    fn compress(state: &mut [u32; 8], block: &[u8]) {
+        compress_256(state, block);
+    }
+
+    fn digest(state: &[u32; 8]) -> Bytes {
+        state[..7]
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .collect()
+    }
+
+    fn state(_digest: &[u8]) -> Option<[u32; 8]> {
+        None
+    }
+}
+
+impl Compression for Sha256Core {
+    type State = [u32; 8];
+
+    const BLOCK_SIZE: usize = 64;
+    const LENGTH_SIZE: usize = 8;
+    const BIG_ENDIAN: bool = true;
+    const INITIAL_STATE: [u32; 8] = [
+        0x6A09_E667,
+        0xBB67_AE85,
+        0x3C6E_F372,
+        0xA54F_F53A,
+        0x510E_527F,
+        0x9B05_688C,
+        0x1F83_D9AB,
+        0x5BE0_CD19,
+    ];
+
+    fn compress(state: &mut [u32; 8], block: &[u8]) {
+        compress_256(state, block);
+    }
+
+    fn digest(state: &[u32; 8]) -> Bytes {
+        state.iter().flat_map(|word| word.to_be_bytes()).collect()
+    }
+
+    fn state(digest: &[u8]) -> Option<[u32; 8]> {
+        if digest.len() != 32 {
+            return None;
+        }
+
+        let mut state = [0; 8];
+        for (word, bytes) in state.iter_mut().zip(digest.chunks(4)) {
+            *word = u32::from_be_bytes(bytes.try_into().ok()?);
+        }
+        Some(state)
+    }
+}
+
+impl Compression for Sha384Core {
+    type State = [u64; 8];
+
+    const BLOCK_SIZE: usize = 128;
+    const LENGTH_SIZE: usize = 16;
+    const BIG_ENDIAN: bool = true;
+    const INITIAL_STATE: [u64; 8] = [
+        0xCBBB_9D5D_C105_9ED8,
+        0x629A_292A_367C_D507,
+        0x9159_015A_3070_DD17,
+        0x152F_ECD8_F70E_5939,
+        0x6733_2667_FFC0_0B31,
+        0x8EB4_4A87_6858_1511,
+        0xDB0C_2E0D_64F9_8FA7,
+        0x47B5_481D_BEFA_4FA4,
+    ];
+
+    fn compress(state: &mut [u64; 8], block: &[u8]) {
+        compress_512(state, block);
+    }
+
+    fn digest(state: &[u64; 8]) -> Bytes {
+        state[..6]
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .collect()
+    }
+
+    fn state(_digest: &[u8]) -> Option<[u64; 8]> {
+        None
+    }
+}
+
+impl Compression for Sha512Core {
+    type State = [u64; 8];
+
+    const BLOCK_SIZE: usize = 128;
+    const LENGTH_SIZE: usize = 16;
+    const BIG_ENDIAN: bool = true;
+    const INITIAL_STATE: [u64; 8] = [
+        0x6A09_E667_F3BC_C908,
+        0xBB67_AE85_84CA_A73B,
+        0x3C6E_F372_FE94_F82B,
+        0xA54F_F53A_5F1D_36F1,
+        0x510E_527F_ADE6_82D1,
+        0x9B05_688C_2B3E_6C1F,
+        0x1F83_D9AB_FB41_BD6B,
+        0x5BE0_CD19_137E_2179,
+    ];
+
+    fn compress(state: &mut [u64; 8], block: &[u8]) {
+        compress_512(state, block);
+    }
+
+    fn digest(state: &[u64; 8]) -> Bytes {
+        state.iter().flat_map(|word| word.to_be_bytes()).collect()
+    }
+
+    fn state(digest: &[u8]) -> Option<[u64; 8]> {
+        if digest.len() != 64 {
+            return None;
+        }
+
+        let mut state = [0; 8];
+        for (word, bytes) in state.iter_mut().zip(digest.chunks(8)) {
+            *word = u64::from_be_bytes(bytes.try_into().ok()?);
+        }
+        Some(state)
+    }
+}
+
+pub fn sha224(data: &[u8]) -> Bytes {
+    merkle_damgard::hash::<Sha224Core>(data)
+}
+
+pub fn sha256(data: &[u8]) -> Bytes {
+    merkle_damgard::hash::<Sha256Core>(data)
+}
+
+pub fn sha384(data: &[u8]) -> Bytes {
+    merkle_damgard::hash::<Sha384Core>(data)
+}
+
+pub fn sha512(data: &[u8]) -> Bytes {
+    merkle_damgard::hash::<Sha512Core>(data)
+}
+
+fn compress_256(state: &mut [u32; 8], block: &[u8]) {
+    let mut w = [0u32; 64];
+    for (word, bytes) in w.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7)
+            ^ w[i - 15].rotate_right(18)
+            ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17)
+            ^ w[i - 2].rotate_right(19)
+            ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (k, word) in K256.iter().zip(w.iter()) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let choose = (e & f) ^ (!e & g);
+        let temp1 = h
+            .wrapping_add(s1)
+            .wrapping_add(choose)
+            .wrapping_add(*k)
+            .wrapping_add(*word);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let majority = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(majority);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+
+    for (register, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *register = register.wrapping_add(value);
+    }
+}
+
+// The same shape as SHA-256 with wider words, different rotations and
+// more rounds.
+fn compress_512(state: &mut [u64; 8], block: &[u8]) {
+    let mut w = [0u64; 80];
+    for (word, bytes) in w.iter_mut().zip(block.chunks(8)) {
+        *word = u64::from_be_bytes([
+            bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5],
+            bytes[6], bytes[7],
+        ]);
+    }
+    for i in 16..80 {
+        let s0 = w[i - 15].rotate_right(1)
+            ^ w[i - 15].rotate_right(8)
+            ^ (w[i - 15] >> 7);
+        let s1 = w[i - 2].rotate_right(19)
+            ^ w[i - 2].rotate_right(61)
+            ^ (w[i - 2] >> 6);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (k, word) in K512.iter().zip(w.iter()) {
+        let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+        let choose = (e & f) ^ (!e & g);
+        let temp1 = h
+            .wrapping_add(s1)
+            .wrapping_add(choose)
+            .wrapping_add(*k)
+            .wrapping_add(*word);
+        let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+        let majority = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(majority);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+
+    for (register, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *register = register.wrapping_add(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode::bytes_to_hex;
+
+    type Hash = fn(&[u8]) -> Bytes;
+
+    const ABC_448: &[u8] =
+        b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+    const ABC_896: &[u8] = b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmn\
+                             hijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+
+    #[test]
+    fn test_vectors() {
+        let cases: [(Hash, &[u8], &str); 12] = [
+            (sha224, b"", "d14a028c2a3a2bc9476102bb288234c415a2b01f828ea62ac5b3e42f"),
+            (sha224, b"abc", "23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7"),
+            (sha224, ABC_448, "75388b16512776cc5dba5da1fd890150b0c6455cb4f58b1952522525"),
+            (sha256, b"", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+            (sha256, b"abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            (sha256, ABC_448, "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"),
+            (sha384, b"", "38b060a751ac96384cd9327eb1b1e36a21fdb71114be07434c0cc7bf63f6e1da274edebfe76f65fbd51ad2f14898b95b"),
+            (sha384, b"abc", "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7"),
+            (sha384, ABC_896, "09330c33f71147e83d192fc782cd1b4753111b173b3b05d22fa08086e3b0f712fcc7c71a557e2db966c3e9fa91746039"),
+            (sha512, b"", "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e"),
+            (sha512, b"abc", "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"),
+            (sha512, ABC_896, "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909"),
+        ];
+
+        for (hash, message, digest) in cases.iter() {
+            assert_eq!(bytes_to_hex(hash(message)), *digest);
+        }
+    }
+
+    #[test]
+    fn test_streaming() {
+        // A million 'a's, fed in uneven pieces
+        let mut sha256 = Sha256::new();
+        let mut sha512 = Sha512::new();
+        let chunk = [b'a'; 999];
+        let mut fed = 0;
+        while fed < 1_000_000 {
+            let len = chunk.len().min(1_000_000 - fed);
+            sha256.update(&chunk[..len]);
+            sha512.update(&chunk[..len]);
+            fed += len;
+        }
+
+        assert_eq!(
+            bytes_to_hex(sha256.finalize()),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+        assert_eq!(
+            bytes_to_hex(sha512.finalize()),
+            "e718483d0ce769644e2e42c7bc15b4638e1f98b13b2044285632a803afa973eb\
+             de0ff244877ea60a4cb0432ce577c31beb009c5c2c49aa2e4eadb217ad8cc09b"
+        );
+    }
+
+    #[test]
+    fn test_from_digest() {
+        let message = b"The quick brown fox jumps over the lazy dog";
+
+        let padding =
+            merkle_damgard::glue_padding::<Sha512Core>(message.len() as u64);
+        assert_eq!((message.len() + padding.len()) % 128, 0);
+        // 344 bits, big endian across all 16 bytes
+        let length = &padding[padding.len() - 16..];
+        assert_eq!(length[..14], [0; 14]);
+        assert_eq!(length[14..], [0x01, 0x58]);
+
+        let glued = [&message[..], &padding].concat();
+        let mut resumed =
+            Sha512::from_digest(&sha512(message), glued.len() as u64).unwrap();
+        resumed.update(b"more");
+        assert_eq!(resumed.finalize(), sha512(&[&glued[..], b"more"].concat()));
+
+        // The truncated variants don't give away their whole state
+        assert!(Sha224::from_digest(&sha224(message), 64).is_none());
+        assert!(Sha384::from_digest(&sha384(message), 128).is_none());
+    }
+}
@@ -2,7 +2,7 @@ use crate::encode::{bytes_to_hex, hex_to_bytes};
 use crate::Bytes;
 
 pub fn byte_xor(a: &Bytes, b: &Bytes) -> Bytes {
-    a.iter().zip(b).map(|(a, b)| (a ^ b)).collect()
+    a.iter().zip(b).map(|(a, b)| a ^ b).collect()
 }
 
 pub fn char_xor(a: &Bytes, b: u8) -> Bytes {
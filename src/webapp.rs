@@ -0,0 +1,539 @@
+//
+// Toy web app pieces for the attacks to pick on.
+//
+// These stand in for a server we can talk to but can't see inside of. Each
+// one keeps its key to itself and only hands out ciphertext.
+//
+
+use crate::cracking::oracle::{Oracle, SignedMessage};
+use crate::random::{OsRng, Rng};
+use crate::{decrypt, encode, encrypt, mac, padding, sha1, Bytes};
+
+// Parse a `k=v&k=v` string, keeping the pairs in order.
+pub fn parse_kv(input: &str) -> Vec<(String, String)> {
+    input
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.find('=') {
+            Some(i) => (pair[..i].to_string(), pair[i + 1..].to_string()),
+            None => (pair.to_string(), String::new()),
+        })
+        .collect()
+}
+
+pub fn encode_kv(pairs: &[(String, String)]) -> String {
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+// Build the cookie for a new user. Metacharacters are dropped so the email
+// can't add fields of its own.
+pub fn profile_for(email: &str) -> String {
+    let email: String =
+        email.chars().filter(|c| *c != '&' && *c != '=').collect();
+
+    encode_kv(&[
+        ("email".to_string(), email),
+        ("uid".to_string(), "10".to_string()),
+        ("role".to_string(), "user".to_string()),
+    ])
+}
+
+/// Hands out ECB encrypted profile cookies for any email address.
+pub struct ProfileService {
+    key: Bytes,
+}
+
+impl ProfileService {
+    pub fn new() -> ProfileService {
+        ProfileService::with_rng(&mut OsRng)
+    }
+
+    pub fn with_rng(rng: &mut impl Rng) -> ProfileService {
+        ProfileService { key: rng.bytes(16) }
+    }
+
+    pub fn encrypt_profile(&self, email: &str) -> Bytes {
+        let cookie = profile_for(email).into_bytes();
+        encrypt::aes_ecb(&cookie, &self.key)
+    }
+
+    pub fn decrypt_profile(&self, ciphertext: &Bytes) -> Vec<(String, String)> {
+        let cookie = decrypt::aes_ecb(ciphertext, &self.key);
+        parse_kv(&String::from_utf8_lossy(&cookie))
+    }
+
+    pub fn role(&self, ciphertext: &Bytes) -> Option<String> {
+        self.decrypt_profile(ciphertext)
+            .into_iter()
+            .find(|(key, _)| key == "role")
+            .map(|(_, value)| value)
+    }
+}
+
+impl Default for ProfileService {
+    fn default() -> ProfileService {
+        ProfileService::new()
+    }
+}
+
+// Anyone can sign up with whatever email they like.
+impl Oracle for ProfileService {
+    type Input = Bytes;
+    type Output = Bytes;
+
+    fn query(&mut self, email: &Bytes) -> Bytes {
+        self.encrypt_profile(&String::from_utf8_lossy(email))
+    }
+}
+
+// Wrap user data in the comment string, quoting out `;` and `=` so it
+// can't add fields of its own.
+pub fn comment_for(userdata: &str) -> String {
+    let quoted = userdata.replace(';', "%3B").replace('=', "%3D");
+    format!(
+        "comment1=cooking%20MCs;userdata={};\
+         comment2=%20like%20a%20pound%20of%20bacon",
+        quoted
+    )
+}
+
+fn comment_is_admin(comment: &Bytes) -> bool {
+    String::from_utf8_lossy(comment)
+        .split(';')
+        .any(|pair| pair == "admin=true")
+}
+
+/// Wraps user data in a CBC encrypted comment string, and checks those
+/// strings for an admin flag.
+pub struct CommentService {
+    key: Bytes,
+    iv: Bytes,
+}
+
+impl CommentService {
+    pub fn new() -> CommentService {
+        CommentService::with_rng(&mut OsRng)
+    }
+
+    pub fn with_rng(rng: &mut impl Rng) -> CommentService {
+        CommentService {
+            key: rng.bytes(16),
+            iv: rng.bytes(16),
+        }
+    }
+
+    pub fn encrypt_userdata(&self, userdata: &str) -> Bytes {
+        let comment = comment_for(userdata).into_bytes();
+        encrypt::aes_cbc(&comment, &self.key, &self.iv)
+    }
+
+    pub fn is_admin(&self, ciphertext: &Bytes) -> bool {
+        comment_is_admin(&decrypt::aes_cbc(ciphertext, &self.key, &self.iv))
+    }
+}
+
+impl Default for CommentService {
+    fn default() -> CommentService {
+        CommentService::new()
+    }
+}
+
+impl Oracle for CommentService {
+    type Input = Bytes;
+    type Output = Bytes;
+
+    fn query(&mut self, userdata: &Bytes) -> Bytes {
+        self.encrypt_userdata(&String::from_utf8_lossy(userdata))
+    }
+}
+
+/// The same comment service, but encrypting with CTR.
+pub struct CtrCommentService {
+    key: Bytes,
+    nonce: u64,
+}
+
+impl CtrCommentService {
+    pub fn new() -> CtrCommentService {
+        CtrCommentService::with_rng(&mut OsRng)
+    }
+
+    pub fn with_rng(rng: &mut impl Rng) -> CtrCommentService {
+        CtrCommentService {
+            key: rng.bytes(16),
+            nonce: rng.next_u64(),
+        }
+    }
+
+    pub fn encrypt_userdata(&self, userdata: &str) -> Bytes {
+        let comment = comment_for(userdata).into_bytes();
+        encrypt::aes_ctr(&comment, &self.key, self.nonce)
+    }
+
+    pub fn is_admin(&self, ciphertext: &Bytes) -> bool {
+        comment_is_admin(&decrypt::aes_ctr(ciphertext, &self.key, self.nonce))
+    }
+}
+
+impl Default for CtrCommentService {
+    fn default() -> CtrCommentService {
+        CtrCommentService::new()
+    }
+}
+
+impl Oracle for CtrCommentService {
+    type Input = Bytes;
+    type Output = Bytes;
+
+    fn query(&mut self, userdata: &Bytes) -> Bytes {
+        self.encrypt_userdata(&String::from_utf8_lossy(userdata))
+    }
+}
+
+/// The CBC comment service again, but someone decided the key would make a
+/// fine IV. When a comment decrypts to anything that isn't ASCII, the error
+/// it returns includes the offending plaintext.
+pub struct KeyIvService {
+    key: Bytes,
+}
+
+impl KeyIvService {
+    pub fn new() -> KeyIvService {
+        KeyIvService::with_rng(&mut OsRng)
+    }
+
+    pub fn with_rng(rng: &mut impl Rng) -> KeyIvService {
+        KeyIvService { key: rng.bytes(16) }
+    }
+
+    pub fn encrypt_userdata(&self, userdata: &str) -> Bytes {
+        let comment = comment_for(userdata).into_bytes();
+        encrypt::aes_cbc(&comment, &self.key, &self.key)
+    }
+
+    pub fn receive(&self, ciphertext: &Bytes) -> Result<bool, Bytes> {
+        let comment = decrypt::aes_cbc(ciphertext, &self.key, &self.key);
+
+        if comment.iter().any(|b| *b > 0x7F) {
+            return Err(comment);
+        }
+
+        Ok(comment_is_admin(&comment))
+    }
+}
+
+impl Default for KeyIvService {
+    fn default() -> KeyIvService {
+        KeyIvService::new()
+    }
+}
+
+// The receiving end, which leaks the plaintext when it complains.
+impl Oracle for KeyIvService {
+    type Input = Bytes;
+    type Output = Option<Bytes>;
+
+    fn query(&mut self, ciphertext: &Bytes) -> Option<Bytes> {
+        self.receive(ciphertext).err()
+    }
+}
+
+const PADDING_STRINGS: [&str; 10] = [
+    "MDAwMDAwTm93IHRoYXQgdGhlIHBhcnR5IGlzIGp1bXBpbmc=",
+    "MDAwMDAxV2l0aCB0aGUgYmFzcyBraWNrZWQgaW4gYW5kIHRoZSBWZWdhJ3MgYXJlIHB1bXBpbic=",
+    "MDAwMDAyUXVpY2sgdG8gdGhlIHBvaW50LCB0byB0aGUgcG9pbnQsIG5vIGZha2luZw==",
+    "MDAwMDAzQ29va2luZyBNQydzIGxpa2UgYSBwb3VuZCBvZiBiYWNvbg==",
+    "MDAwMDA0QnVybmluZyAnZW0sIGlmIHlvdSBhaW4ndCBxdWljayBhbmQgbmltYmxl",
+    "MDAwMDA1SSBnbyBjcmF6eSB3aGVuIEkgaGVhciBhIGN5bWJhbA==",
+    "MDAwMDA2QW5kIGEgaGlnaCBoYXQgd2l0aCBhIHNvdXBlZCB1cCB0ZW1wbw==",
+    "MDAwMDA3SSdtIG9uIGEgcm9sbCwgaXQncyB0aW1lIHRvIGdvIHNvbG8=",
+    "MDAwMDA4b2xsaW4nIGluIG15IGZpdmUgcG9pbnQgb2g=",
+    "MDAwMDA5aXRoIG15IHJhZy10b3AgZG93biBzbyBteSBoYWlyIGNhbiBibG93",
+];
+
+/// Encrypts one of ten secret strings under CBC, and will tell anyone
+/// whether a ciphertext decrypts with valid padding. It keeps its `Rng` to
+/// pick the string and IV for each encryption.
+pub struct PaddingService<R: Rng = OsRng> {
+    key: Bytes,
+    rng: R,
+}
+
+impl PaddingService {
+    pub fn new() -> PaddingService {
+        PaddingService::with_rng(OsRng)
+    }
+
+    pub fn strings() -> Vec<Bytes> {
+        PADDING_STRINGS
+            .iter()
+            .map(|s| encode::b64_to_bytes(s))
+            .collect()
+    }
+}
+
+impl<R: Rng> PaddingService<R> {
+    /// Unlike the other services, which only draw a key when they're built,
+    /// this one keeps drawing for every `encrypt_random`, so it takes the
+    /// generator by value rather than borrowing it.
+    pub fn with_rng(mut rng: R) -> PaddingService<R> {
+        PaddingService {
+            key: rng.bytes(16),
+            rng,
+        }
+    }
+
+    // Pick a string at random and return `(iv, ciphertext)`.
+    pub fn encrypt_random(&mut self) -> (Bytes, Bytes) {
+        let strings = PaddingService::strings();
+        let plaintext = &strings[self.rng.range(0, strings.len())];
+        let iv = self.rng.bytes(16);
+        let ciphertext = encrypt::aes_cbc(plaintext, &self.key, &iv);
+
+        (iv, ciphertext)
+    }
+
+    pub fn padding_valid(&self, iv: &Bytes, ciphertext: &Bytes) -> bool {
+        let plaintext = decrypt::aes_cbc_padded(ciphertext, &self.key, iv);
+        padding::strip_pkcs7(&plaintext, 16).is_some()
+    }
+}
+
+impl Default for PaddingService {
+    fn default() -> PaddingService {
+        PaddingService::new()
+    }
+}
+
+impl<R: Rng> Oracle for PaddingService<R> {
+    type Input = (Bytes, Bytes);
+    type Output = bool;
+
+    fn query(&mut self, (iv, ciphertext): &(Bytes, Bytes)) -> bool {
+        self.padding_valid(iv, ciphertext)
+    }
+}
+
+/// Stores CTR encrypted data and lets anyone overwrite part of it without
+/// knowing the key, like seeking and writing on an encrypted disk.
+pub struct DiskService {
+    key: Bytes,
+    nonce: u64,
+}
+
+impl DiskService {
+    pub fn new() -> DiskService {
+        DiskService::with_rng(&mut OsRng)
+    }
+
+    pub fn with_rng(rng: &mut impl Rng) -> DiskService {
+        DiskService {
+            key: rng.bytes(16),
+            nonce: rng.next_u64(),
+        }
+    }
+
+    pub fn encrypt(&self, plaintext: &Bytes) -> Bytes {
+        encrypt::aes_ctr(plaintext, &self.key, self.nonce)
+    }
+
+    pub fn edit(
+        &self,
+        ciphertext: &Bytes,
+        offset: usize,
+        newtext: &Bytes,
+    ) -> Bytes {
+        encrypt::aes_ctr_edit(
+            ciphertext, &self.key, self.nonce, offset, newtext,
+        )
+    }
+}
+
+impl Default for DiskService {
+    fn default() -> DiskService {
+        DiskService::new()
+    }
+}
+
+impl Oracle for DiskService {
+    type Input = (Bytes, usize, Bytes);
+    type Output = Bytes;
+
+    fn query(
+        &mut self,
+        (ciphertext, offset, newtext): &(Bytes, usize, Bytes),
+    ) -> Bytes {
+        self.edit(ciphertext, *offset, newtext)
+    }
+}
+
+const COOKING_MCS: &str = "comment1=cooking%20MCs;userdata=foo;\
+                           comment2=%20like%20a%20pound%20of%20bacon";
+
+/// Signs messages with a secret-prefix MAC under a key of unknown length,
+/// and checks signed messages for an admin flag. SHA-1 unless told
+/// otherwise.
+pub struct MacService {
+    key: Bytes,
+    hash: fn(&[u8]) -> Bytes,
+}
+
+impl MacService {
+    pub fn new() -> MacService {
+        MacService::with_rng(&mut OsRng)
+    }
+
+    pub fn with_rng(rng: &mut impl Rng) -> MacService {
+        MacService::with_hash(rng, sha1::sha1)
+    }
+
+    pub fn with_hash(
+        rng: &mut impl Rng,
+        hash: fn(&[u8]) -> Bytes,
+    ) -> MacService {
+        let len = rng.range(4, 33);
+        MacService {
+            key: rng.bytes(len),
+            hash,
+        }
+    }
+
+    // The message and MAC an attacker gets to see.
+    pub fn signed_comment(&self) -> (Bytes, Bytes) {
+        let message = COOKING_MCS.as_bytes().to_vec();
+        let mac = self.sign(&message);
+        (message, mac)
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Bytes {
+        mac::secret_prefix_mac_with(self.hash, &self.key, message)
+    }
+
+    pub fn verify(&self, message: &[u8], mac: &[u8]) -> bool {
+        mac::verify_secret_prefix_mac_with(self.hash, &self.key, message, mac)
+    }
+
+    pub fn is_admin(&self, message: &Bytes, mac: &[u8]) -> bool {
+        self.verify(message, mac) && comment_is_admin(message)
+    }
+}
+
+impl Default for MacService {
+    fn default() -> MacService {
+        MacService::new()
+    }
+}
+
+impl Oracle for MacService {
+    type Input = SignedMessage;
+    type Output = bool;
+
+    fn query(&mut self, signed: &SignedMessage) -> bool {
+        self.verify(&signed.message, &signed.mac)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::SeededRng;
+
+    #[test]
+    fn test_parse_kv() {
+        assert_eq!(
+            parse_kv("foo=bar&baz=qux&zap=zazzle"),
+            vec![
+                ("foo".to_string(), "bar".to_string()),
+                ("baz".to_string(), "qux".to_string()),
+                ("zap".to_string(), "zazzle".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_kv("a=b=c&flag"),
+            vec![
+                ("a".to_string(), "b=c".to_string()),
+                ("flag".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_profile_for() {
+        assert_eq!(
+            profile_for("foo@bar.com"),
+            "email=foo@bar.com&uid=10&role=user"
+        );
+        assert_eq!(
+            profile_for("foo@bar.com&role=admin"),
+            "email=foo@bar.comroleadmin&uid=10&role=user"
+        );
+        assert_eq!(
+            encode_kv(&parse_kv(&profile_for("foo@bar.com"))),
+            profile_for("foo@bar.com")
+        );
+    }
+
+    #[test]
+    fn test_comment_service() {
+        let service = CommentService::new();
+
+        assert!(!service.is_admin(&service.encrypt_userdata(";admin=true;")));
+        assert!(!service.is_admin(&service.encrypt_userdata("x;admin=true")));
+        assert_eq!(service.encrypt_userdata(";").len(), 80);
+    }
+
+    #[test]
+    fn test_disk_service() {
+        let service = DiskService::new();
+        let plaintext = "some data on an encrypted disk".as_bytes().to_vec();
+        let ciphertext = service.encrypt(&plaintext);
+
+        let edited = service.edit(&ciphertext, 5, &"DATA".as_bytes().to_vec());
+        assert_eq!(
+            edited,
+            service
+                .encrypt(&"some DATA on an encrypted disk".as_bytes().to_vec())
+        );
+
+        let grown =
+            service.edit(&ciphertext, 26, &"disk drive".as_bytes().to_vec());
+        assert_eq!(
+            grown,
+            service.encrypt(
+                &"some data on an encrypted disk drive".as_bytes().to_vec()
+            )
+        );
+
+        let gapped = service.edit(&ciphertext, 35, &"!".as_bytes().to_vec());
+        let mut expected = plaintext.clone();
+        expected.extend(vec![0; 5]);
+        expected.push(b'!');
+        assert_eq!(gapped, service.encrypt(&expected));
+    }
+
+    #[test]
+    fn test_with_rng() {
+        let a = ProfileService::with_rng(&mut SeededRng::new(1));
+        let b = ProfileService::with_rng(&mut SeededRng::new(1));
+        assert_eq!(a.encrypt_profile("a@b.c"), b.encrypt_profile("a@b.c"));
+
+        let mut a = PaddingService::with_rng(SeededRng::new(2));
+        let mut b = PaddingService::with_rng(SeededRng::new(2));
+        for _ in 0..5 {
+            assert_eq!(a.encrypt_random(), b.encrypt_random());
+        }
+    }
+
+    #[test]
+    fn test_profile_service() {
+        let service = ProfileService::new();
+        let ciphertext = service.encrypt_profile("foo@bar.com");
+
+        assert_eq!(ciphertext.len(), 48);
+        assert_eq!(service.role(&ciphertext), Some("user".to_string()));
+    }
+}